@@ -38,16 +38,34 @@ impl AdfDisk {
     /// Returns a slice covering `count` blocks starting at `f_block`.
     /// Panics if the range exceeds the image size.
     pub fn load_blocks(&self, f_block: u32, count: u32) -> &[u8] {
-        let start = (f_block as usize) * BLOCK_SIZE;
-        let end = start + (count as usize) * BLOCK_SIZE;
-        assert!(
-            end <= self.data.len(),
-            "ADF block range [{}, {}) exceeds image size {}",
-            f_block,
-            f_block + count,
-            self.data.len() / BLOCK_SIZE
-        );
-        &self.data[start..end]
+        match self.read_range(f_block, count) {
+            Ok(slice) => slice,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Returns a slice covering `count` blocks starting at `start_block`,
+    /// or an error if the range runs past the end of the image.
+    pub fn read_range(&self, start_block: u32, count: u32) -> Result<&[u8]> {
+        let start = (start_block as usize) * BLOCK_SIZE;
+        let end = (count as usize)
+            .checked_mul(BLOCK_SIZE)
+            .and_then(|len| start.checked_add(len))
+            .filter(|&end| end <= self.data.len());
+        match end {
+            Some(end) => Ok(&self.data[start..end]),
+            None => bail!(
+                "ADF block range [{}, {}) exceeds image size {}",
+                start_block,
+                start_block as u64 + count as u64,
+                self.num_blocks()
+            ),
+        }
+    }
+
+    /// Returns a single block's bytes, or an error if `n` is out of range.
+    pub fn read_block(&self, n: u32) -> Result<&[u8]> {
+        self.read_range(n, 1)
     }
 
     /// Returns a single block's bytes.
@@ -97,6 +115,24 @@ mod tests {
         assert_eq!(adf.num_blocks(), 10);
     }
 
+    #[test]
+    fn test_read_block_and_range() {
+        let adf = make_adf(8);
+        assert_eq!(adf.read_block(5).unwrap()[0], 5);
+        let slice = adf.read_range(6, 2).unwrap();
+        assert_eq!(slice.len(), 2 * BLOCK_SIZE);
+        assert_eq!(slice[BLOCK_SIZE], 7);
+    }
+
+    #[test]
+    fn test_read_out_of_range_errors() {
+        let adf = make_adf(4);
+        assert!(adf.read_block(4).is_err());
+        assert!(adf.read_range(3, 2).is_err());
+        assert!(adf.read_range(0, u32::MAX).is_err());
+        assert!(adf.read_range(4, 0).is_ok());
+    }
+
     #[test]
     #[should_panic]
     fn test_out_of_range_panics() {
@@ -4,6 +4,7 @@
 // - extract_region: copy a sub-rectangle out of a BitMap
 // - set_plane: replace a single bitplane's data
 // - blt_copy_region: copy a sub-rectangle between two BitMaps (plane-masked)
+// - compose: draw keyed sprites over a copy of a background
 //
 // All operations work on bitplane data (Vec<Vec<u8>>) and are SDL3-independent.
 
@@ -173,6 +174,34 @@ pub fn blt_copy_region(
     dst.invalidate_cache();
 }
 
+/// Copy `background` and draw each sprite onto it at its (x, y) position,
+/// in order, so the result can be uploaded as a single texture.
+///
+/// Sprite pixels matching the sprite's key color are left transparent, and
+/// any part of a sprite outside the background is clipped.
+pub fn compose(background: &BitMap, sprites: &[(&BitMap, (i32, i32), Option<usize>)]) -> BitMap {
+    let mut merged = background.clone();
+    for (sprite, (x, y), key_color) in sprites {
+        for sy in 0..sprite.height {
+            let dst_y = *y as i64 + sy as i64;
+            if dst_y < 0 || dst_y >= merged.height as i64 {
+                continue;
+            }
+            for sx in 0..sprite.width {
+                let dst_x = *x as i64 + sx as i64;
+                if dst_x < 0 || dst_x >= merged.width as i64 {
+                    continue;
+                }
+                let index = sprite.get_pixel(sx, sy);
+                if Some(index) != *key_color {
+                    merged.set_pixel(dst_x as usize, dst_y as usize, index);
+                }
+            }
+        }
+    }
+    merged
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -294,4 +323,40 @@ mod tests {
         assert_eq!(dst.planes[0], src.planes[0]);
         assert_eq!(dst.planes[1], src.planes[1]);
     }
+
+    #[test]
+    fn test_compose_sprite_over_background() {
+        let mut background = BitMap::build(16, 8, 3).unwrap();
+        for yy in 0..8 {
+            for xx in 0..16 {
+                background.set_pixel(xx, yy, 1);
+            }
+        }
+        // 3x2 sprite of index 5 with a key-colored (0) hole at (1, 0)
+        let mut sprite = BitMap::build(3, 2, 3).unwrap();
+        for yy in 0..2 {
+            for xx in 0..3 {
+                sprite.set_pixel(xx, yy, 5);
+            }
+        }
+        sprite.set_pixel(1, 0, 0);
+
+        // second copy hangs off the right edge and is clipped
+        let merged = compose(
+            &background,
+            &[(&sprite, (4, 3), Some(0)), (&sprite, (14, -1), Some(0))],
+        );
+
+        assert_eq!(merged.get_pixel(4, 3), 5);
+        assert_eq!(merged.get_pixel(5, 3), 1); // key color shows the background
+        assert_eq!(merged.get_pixel(6, 4), 5);
+        assert_eq!(merged.get_pixel(7, 3), 1);
+        assert_eq!(merged.get_pixel(4, 5), 1);
+        assert_eq!(merged.get_pixel(14, 0), 5);
+        assert_eq!(merged.get_pixel(15, 0), 5);
+        assert_eq!(merged.get_pixel(15, 1), 1);
+
+        // the background itself is untouched
+        assert_eq!(background.get_pixel(4, 3), 1);
+    }
 }
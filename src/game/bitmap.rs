@@ -1,10 +1,27 @@
 // Classes and utilities for working with bitmaps and bitplanes
 
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell};
 
+use sdl3::pixels::PixelFormat;
+use sdl3::rect::Rect;
+use sdl3::surface::Surface;
 use serde::Deserialize;
 
 use crate::game::colors::Palette;
+use crate::game::font::DiskFont;
+use crate::game::gif;
+
+use std::path::Path;
+
+// Pixel codes produced by `BitMap::edge_map`.
+pub const EDGE_BACKGROUND: usize = 0;
+pub const EDGE_INTERIOR: usize = 1;
+pub const EDGE_HIGHLIGHT: usize = 2; // left or top edge
+pub const EDGE_SHADOW: usize = 3; // right or bottom edge
+
+// 64-bit FNV-1a parameters for `BitMap::content_hash`.
+const FNV_OFFSET_BASIS: u64 = 0xCBF29CE484222325;
+const FNV_PRIME: u64 = 0x100000001B3;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct BitMap {
@@ -147,6 +164,482 @@ impl BitMap {
         (self.width, self.height)
     }
 
+    /// Check that the plane layout matches the declared size: one plane per
+    /// depth level, each `stride * height` bytes, with rows wide enough for
+    /// `width` pixels. Catches hand-built bitmaps before they cause an index
+    /// panic during conversion.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.depth < 1 || self.depth > 24 {
+            return Err(format!("BitMap depth {} must be 1 to 24", self.depth));
+        }
+        if self.planes.len() != self.depth {
+            return Err(format!(
+                "BitMap has {} planes but depth {}",
+                self.planes.len(),
+                self.depth
+            ));
+        }
+        if self.stride * 8 < self.width {
+            return Err(format!(
+                "BitMap stride {} bytes is too small for width {}",
+                self.stride, self.width
+            ));
+        }
+        let plane_size = self.stride * self.height;
+        for (pp, plane) in self.planes.iter().enumerate() {
+            if plane.len() != plane_size {
+                return Err(format!(
+                    "BitMap plane {} is {} bytes, expected {} (stride {} * height {})",
+                    pp,
+                    plane.len(),
+                    plane_size,
+                    self.stride,
+                    self.height
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Stable 64-bit FNV-1a hash of the dimensions and plane bytes, for
+    /// detecting content changes. The cached index buffer is not included.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut feed = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash = (hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+            }
+        };
+        for value in [
+            self.width,
+            self.height,
+            self.depth,
+            self.stride,
+            self.planes.len(),
+        ] {
+            feed(&(value as u64).to_le_bytes());
+        }
+        for plane in &self.planes {
+            feed(&(plane.len() as u64).to_le_bytes());
+            feed(plane);
+        }
+        hash
+    }
+
+    /// Read the palette index of the pixel at (x, y) from the bitplanes.
+    pub fn get_pixel(&self, x: usize, y: usize) -> usize {
+        let byte_index = y * self.stride + (x >> 3);
+        let bit_index = 7 - (x & 0x07);
+        let mut pixel_index: usize = 0;
+        for (pp, plane) in self.planes.iter().enumerate() {
+            let bit = (plane[byte_index] >> bit_index) & 0x01;
+            pixel_index |= (bit as usize) << pp;
+        }
+        pixel_index
+    }
+
+    /// Write a palette index to the pixel at (x, y), one bit per plane.
+    /// Bits of `index` beyond the bitmap depth are ignored.
+    pub fn set_pixel(&mut self, x: usize, y: usize, index: usize) {
+        let byte_index = y * self.stride + (x >> 3);
+        let mask = 0x80u8 >> (x & 0x07);
+        for (pp, plane) in self.planes.iter_mut().enumerate() {
+            if index & (1 << pp) != 0 {
+                plane[byte_index] |= mask;
+            } else {
+                plane[byte_index] &= !mask;
+            }
+        }
+        self.invalidate_cache();
+    }
+
+    /// Tight rectangle enclosing every pixel whose index differs from
+    /// `background_index`, or `None` if the bitmap is entirely background.
+    pub fn content_bounds(&self, background_index: usize) -> Option<Rect> {
+        let mut extent: Option<(usize, usize, usize, usize)> = None;
+        for yy in 0..self.height {
+            for xx in 0..self.width {
+                if self.get_pixel(xx, yy) == background_index {
+                    continue;
+                }
+                extent = Some(match extent {
+                    None => (xx, yy, xx, yy),
+                    Some((l, t, r, b)) => (l.min(xx), t.min(yy), r.max(xx), b.max(yy)),
+                });
+            }
+        }
+
+        extent.map(|(l, t, r, b)| {
+            Rect::new(l as i32, t as i32, (r - l + 1) as u32, (b - t + 1) as u32)
+        })
+    }
+
+    /// Copy of this bitmap trimmed to its `content_bounds`, with the same
+    /// depth. Returns `None` if the bitmap is entirely background.
+    pub fn cropped(&self, background_index: usize) -> Option<BitMap> {
+        let bounds = self.content_bounds(background_index)?;
+        let width = bounds.width() as usize;
+        let height = bounds.height() as usize;
+        let stride = ((width + 15) >> 3) & !1_usize;
+        let mut cropped = BitMap::from_planes(
+            vec![vec![0; stride * height]; self.depth],
+            width,
+            height,
+            self.depth,
+            stride,
+        );
+
+        for yy in 0..height {
+            for xx in 0..width {
+                let index = self.get_pixel(bounds.x() as usize + xx, bounds.y() as usize + yy);
+                cropped.set_pixel(xx, yy, index);
+            }
+        }
+        Some(cropped)
+    }
+
+    /// Shift every plane by (`dx`, `dy`) pixels. With `wrap` the pixels pushed
+    /// off one edge reappear on the opposite edge, otherwise the vacated
+    /// edges are filled with index 0.
+    pub fn scroll(&mut self, dx: isize, dy: isize, wrap: bool) {
+        let width = self.width as isize;
+        let height = self.height as isize;
+        for plane in self.planes.iter_mut() {
+            let mut shifted = vec![0u8; plane.len()];
+            for yy in 0..height {
+                for xx in 0..width {
+                    let (mut src_x, mut src_y) = (xx - dx, yy - dy);
+                    if wrap {
+                        src_x = src_x.rem_euclid(width);
+                        src_y = src_y.rem_euclid(height);
+                    } else if src_x < 0 || src_x >= width || src_y < 0 || src_y >= height {
+                        continue;
+                    }
+                    let src_byte = src_y as usize * self.stride + (src_x as usize >> 3);
+                    if plane[src_byte] & (0x80 >> (src_x & 0x07)) != 0 {
+                        shifted[yy as usize * self.stride + (xx as usize >> 3)] |=
+                            0x80 >> (xx & 0x07);
+                    }
+                }
+            }
+            *plane = shifted;
+        }
+        self.invalidate_cache();
+    }
+
+    /// Classify every pixel for simple emboss shading, as a depth-2 bitmap of
+    /// `EDGE_*` codes. A non-background pixel with background to its left or
+    /// above is a highlight edge, otherwise one with background to its right
+    /// or below is a shadow edge, otherwise it is interior. Pixels outside the
+    /// bitmap count as background.
+    pub fn edge_map(&self, background_index: usize) -> BitMap {
+        let mut edges = BitMap::build(self.width, self.height, 2).unwrap();
+        let indices = self.index_buffer();
+        let is_background = |x: isize, y: isize| {
+            x < 0
+                || y < 0
+                || x >= self.width as isize
+                || y >= self.height as isize
+                || indices[y as usize * self.width + x as usize] == background_index
+        };
+
+        for yy in 0..self.height as isize {
+            for xx in 0..self.width as isize {
+                let code = if is_background(xx, yy) {
+                    EDGE_BACKGROUND
+                } else if is_background(xx - 1, yy) || is_background(xx, yy - 1) {
+                    EDGE_HIGHLIGHT
+                } else if is_background(xx + 1, yy) || is_background(xx, yy + 1) {
+                    EDGE_SHADOW
+                } else {
+                    EDGE_INTERIOR
+                };
+                edges.set_pixel(xx as usize, yy as usize, code);
+            }
+        }
+        edges
+    }
+
+    /// Drop shadow for this sprite: every pixel that isn't `key_color` becomes
+    /// `shadow_index`, moved by `offset`, on a `key_color` background. The
+    /// result grows by the offset so it covers both the sprite and its
+    /// shadow; the sprite's own top-left sits at `(max(0, -dx), max(0, -dy))`
+    /// in it, so blitting the shadow there first grounds the sprite. Depth is
+    /// unchanged, so both indices must fit it.
+    pub fn shadow(&self, key_color: usize, offset: (i32, i32), shadow_index: usize) -> BitMap {
+        let (dx, dy) = offset;
+        let width = self.width + dx.unsigned_abs() as usize;
+        let height = self.height + dy.unsigned_abs() as usize;
+        let stride = ((width + 15) >> 3) & !1_usize;
+        let planes = (0..self.depth)
+            .map(|pp| {
+                let fill = if key_color & (1 << pp) != 0 {
+                    0xFF
+                } else {
+                    0x00
+                };
+                vec![fill; stride * height]
+            })
+            .collect();
+        let mut shadow = BitMap::from_planes(planes, width, height, self.depth, stride);
+
+        // the shadow lands `offset` away from wherever the sprite sits
+        let shadow_x = dx.max(0) as usize;
+        let shadow_y = dy.max(0) as usize;
+        for (xx, yy, index) in self.indices() {
+            if index != key_color {
+                shadow.set_pixel(shadow_x + xx, shadow_y + yy, shadow_index);
+            }
+        }
+        shadow
+    }
+
+    /// Copy of this bitmap enlarged by an integer `factor`, each pixel becoming
+    /// a `factor` x `factor` block. A factor of 0 is an error.
+    pub fn scale_nearest(&self, factor: usize) -> Result<BitMap, String> {
+        if factor == 0 {
+            return Err("BitMap scale factor must be at least 1".to_string());
+        }
+        let width = self.width * factor;
+        let height = self.height * factor;
+        let stride = ((width + 15) >> 3) & !1_usize;
+        let mut scaled = BitMap::from_planes(
+            vec![vec![0; stride * height]; self.depth],
+            width,
+            height,
+            self.depth,
+            stride,
+        );
+        for yy in 0..height {
+            for xx in 0..width {
+                scaled.set_pixel(xx, yy, self.get_pixel(xx / factor, yy / factor));
+            }
+        }
+        Ok(scaled)
+    }
+
+    /// Copy of this bitmap resized to `new_width` x `new_height` with
+    /// nearest-neighbor sampling, so indices are never blended. Each target
+    /// pixel takes the source pixel its top-left corner falls in. A zero
+    /// target dimension is an error.
+    pub fn resize_nearest(&self, new_width: usize, new_height: usize) -> Result<BitMap, String> {
+        if new_width == 0 || new_height == 0 {
+            return Err(format!(
+                "BitMap cannot be resized to {}x{}",
+                new_width, new_height
+            ));
+        }
+        let stride = ((new_width + 15) >> 3) & !1_usize;
+        let mut resized = BitMap::from_planes(
+            vec![vec![0; stride * new_height]; self.depth],
+            new_width,
+            new_height,
+            self.depth,
+            stride,
+        );
+        for yy in 0..new_height {
+            let src_y = yy * self.height / new_height;
+            for xx in 0..new_width {
+                let src_x = xx * self.width / new_width;
+                resized.set_pixel(xx, yy, self.get_pixel(src_x, src_y));
+            }
+        }
+        Ok(resized)
+    }
+
+    /// Copy of this bitmap with `new_depth` planes, adding zero-filled high
+    /// planes so every pixel keeps its index. Reducing depth is an error;
+    /// remap the indices first instead.
+    pub fn to_depth(&self, new_depth: usize) -> Result<BitMap, String> {
+        if new_depth < self.depth {
+            return Err(format!(
+                "Cannot reduce BitMap depth from {} to {}",
+                self.depth, new_depth
+            ));
+        }
+        if new_depth > 24 {
+            return Err(format!("BitMap depth {} is above 24", new_depth));
+        }
+        let mut planes = self.planes.clone();
+        planes.resize(new_depth, vec![0; self.stride * self.height]);
+        Ok(BitMap::from_planes(
+            planes,
+            self.width,
+            self.height,
+            new_depth,
+            self.stride,
+        ))
+    }
+
+    /// Assemble a 24-plane deep bitmap from three same-sized channel bitmaps,
+    /// each pixel index being that channel's intensity. Planes follow the deep
+    /// ILBM order: 0-7 red, 8-15 green, 16-23 blue, least significant first.
+    /// Channels shallower than 8 planes fill the high bits of their byte, so a
+    /// pixel's index reads back as `r | g << 8 | b << 16`.
+    pub fn from_channels(r: &BitMap, g: &BitMap, b: &BitMap) -> Result<BitMap, String> {
+        for (name, channel) in [("red", r), ("green", g), ("blue", b)] {
+            if (channel.width, channel.height, channel.stride) != (r.width, r.height, r.stride) {
+                return Err(format!(
+                    "{} channel is {}x{}, expected {}x{} like the red channel",
+                    name, channel.width, channel.height, r.width, r.height
+                ));
+            }
+            if channel.depth < 1 || channel.depth > 8 {
+                return Err(format!(
+                    "{} channel depth {} must be 1 to 8",
+                    name, channel.depth
+                ));
+            }
+            channel.validate()?;
+        }
+
+        let plane_size = r.stride * r.height;
+        let mut planes: Vec<Vec<u8>> = Vec::with_capacity(24);
+        for channel in [r, g, b] {
+            planes.extend(std::iter::repeat_n(vec![0; plane_size], 8 - channel.depth));
+            planes.extend(channel.planes.iter().cloned());
+        }
+        Ok(BitMap::from_planes(planes, r.width, r.height, 24, r.stride))
+    }
+
+    /// Composite this bitmap with `other` through a 1-bit coverage `mask`
+    /// laid out like a single plane (`stride * height` bytes). Where a mask
+    /// bit is set the pixel comes from `other`, otherwise from `self`.
+    ///
+    /// Fails if the bitmaps differ in size or depth, or the mask is not
+    /// plane sized.
+    pub fn blend_masked(&self, other: &BitMap, mask: &[u8]) -> Result<BitMap, String> {
+        if self.width != other.width
+            || self.height != other.height
+            || self.depth != other.depth
+            || self.stride != other.stride
+        {
+            return Err(format!(
+                "blend_masked: {}x{}x{} bitmap can't blend with {}x{}x{}",
+                self.width, self.height, self.depth, other.width, other.height, other.depth
+            ));
+        }
+        let plane_size = self.stride * self.height;
+        if mask.len() != plane_size {
+            return Err(format!(
+                "blend_masked: mask has {} bytes, expected one plane of {}",
+                mask.len(),
+                plane_size
+            ));
+        }
+
+        let planes = self
+            .planes
+            .iter()
+            .zip(other.planes.iter())
+            .map(|(ours, theirs)| {
+                ours.iter()
+                    .zip(theirs.iter())
+                    .zip(mask.iter())
+                    .map(|((a, b), m)| (a & !m) | (b & m))
+                    .collect()
+            })
+            .collect();
+        Ok(BitMap::from_planes(
+            planes,
+            self.width,
+            self.height,
+            self.depth,
+            self.stride,
+        ))
+    }
+
+    /// Set every pixel outside a 1-bit `stencil` to `keep_index_outside`,
+    /// e.g. for fog of war. The stencil is laid out like a single plane
+    /// (`stride * height` bytes); set bits keep the pixel, clear bits replace
+    /// it. Bits of the index beyond the bitmap depth are ignored.
+    ///
+    /// Fails, leaving the bitmap untouched, if the stencil is not plane sized.
+    pub fn apply_stencil(
+        &mut self,
+        stencil: &[u8],
+        keep_index_outside: usize,
+    ) -> Result<(), String> {
+        let plane_size = self.stride * self.height;
+        if stencil.len() != plane_size {
+            return Err(format!(
+                "apply_stencil: stencil has {} bytes, expected one plane of {}",
+                stencil.len(),
+                plane_size
+            ));
+        }
+        for (pp, plane) in self.planes.iter_mut().enumerate() {
+            let fill = if keep_index_outside & (1 << pp) != 0 {
+                0xFF
+            } else {
+                0x00
+            };
+            for (byte, keep) in plane.iter_mut().zip(stencil.iter()) {
+                *byte = (*byte & keep) | (fill & !keep);
+            }
+        }
+        self.invalidate_cache();
+        Ok(())
+    }
+
+    /// Save the bitmap as a still GIF using `palette` for its colors, with
+    /// `transparent_index` (if any) marked transparent. See `gif::save_animated_gif`
+    /// for multi-frame output.
+    pub fn save_gif(
+        &self,
+        palette: &Palette,
+        transparent_index: Option<usize>,
+        path: &Path,
+    ) -> Result<(), String> {
+        gif::save_animated_gif(
+            std::slice::from_ref(self),
+            palette,
+            &[],
+            transparent_index,
+            path,
+        )
+    }
+
+    /// Draw `s` into the bitmap with `font`, setting every pixel covered by a
+    /// set glyph bit to `index`. `(x, y)` is the top-left of the text cell (not
+    /// the baseline). Glyphs advance by the font's spacing table (or `x_size`
+    /// for monospace fonts), characters outside the font are skipped, and
+    /// anything past the bitmap edges is clipped.
+    pub fn draw_text(&mut self, font: &DiskFont, s: &str, x: usize, y: usize, index: usize) {
+        let mut cursor = x as isize;
+        for cc in s.as_bytes() {
+            if *cc < font.lo_char || *cc > font.hi_char {
+                continue;
+            }
+            let cc_index = (cc - font.lo_char) as usize;
+            let (bit_offset, bit_width) = font.char_loc[cc_index];
+            let (kern, space) = if font.is_proportional() {
+                (font.char_kern[cc_index], font.char_space[cc_index])
+            } else {
+                (0, font.x_size as isize)
+            };
+
+            let glyph_x = cursor + kern;
+            for row in 0..font.y_size {
+                let dst_y = y + row;
+                if dst_y >= self.height {
+                    break;
+                }
+                let row_start = font.modulo * row + bit_offset;
+                for col in 0..bit_width {
+                    let dst_x = glyph_x + col as isize;
+                    if dst_x < 0 || dst_x as usize >= self.width {
+                        continue;
+                    }
+                    if font.char_data[row_start + col] != 0 {
+                        self.set_pixel(dst_x as usize, dst_y, index);
+                    }
+                }
+            }
+            cursor += space;
+        }
+    }
+
     /**
      * Create a new BitMap with planes preallocated and ready to use.
      * The planes are zero initialized.
@@ -178,6 +671,100 @@ impl BitMap {
         Ok(bitmap)
     }
 
+    /// Palette index of every pixel in row-major order, built from the planes
+    /// on first use and cached until the bitmap is modified.
+    fn index_buffer(&self) -> Ref<'_, Vec<usize>> {
+        // optimization: reverse iterate over the planes and build an index buffer directly from plane data
+        if self.index_buffer.borrow().is_none() {
+            // build index buffer
+            let mut index_buffer: Vec<usize> = Vec::with_capacity(self.width * self.height);
+            for yy in 0..self.height {
+                for xx in 0..self.width {
+                    let mut pixel_index: usize = 0;
+                    for pp in 0..self.depth {
+                        let plane = &self.planes[pp];
+                        let byte_index = yy * self.stride + (xx >> 3);
+                        let bit_index = 7 - (xx & 0x07);
+                        let bit = (plane[byte_index] >> bit_index) & 0x01;
+                        pixel_index |= (bit as usize) << pp;
+                    }
+                    index_buffer.push(pixel_index);
+                }
+            }
+            // cache it
+            *self.index_buffer.borrow_mut() = Some(index_buffer);
+        }
+        Ref::map(self.index_buffer.borrow(), |buffer| {
+            buffer.as_ref().unwrap()
+        })
+    }
+
+    /// Iterate over every pixel as `(x, y, index)` in row-major order, using
+    /// the cached index buffer rather than converting to RGBA.
+    pub fn indices(&self) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        let width = self.width;
+        let buffer = self.index_buffer();
+        (0..self.width * self.height).map(move |i| (i % width, i / width, buffer[i]))
+    }
+
+    /// Number of pixels using each palette index, `1 << depth` entries long,
+    /// counted from the cached index buffer.
+    pub fn histogram(&self) -> Vec<usize> {
+        let mut counts: Vec<usize> = vec![0; 1 << self.depth];
+        for &index in self.index_buffer().iter() {
+            counts[index] += 1;
+        }
+        counts
+    }
+
+    /// Chunky copy of the pixels: one palette index byte per pixel in
+    /// row-major order, taken from the cached index buffer. Only meaningful
+    /// for depth 8 or less; higher index bits are dropped.
+    pub fn to_chunky(&self) -> Vec<u8> {
+        self.index_buffer()
+            .iter()
+            .map(|&index| index as u8)
+            .collect()
+    }
+
+    /// Build a planar bitmap from chunky data, one palette index byte per
+    /// pixel in row-major order; the reverse of `to_chunky`. Stride is padded
+    /// to a WORD boundary as in `build`, and index bits beyond `depth` are
+    /// ignored.
+    pub fn from_chunky(
+        data: &[u8],
+        width: usize,
+        height: usize,
+        depth: usize,
+    ) -> Result<BitMap, String> {
+        if !(1..=8).contains(&depth) {
+            return Err(format!("Chunky BitMap depth {} must be 1 to 8", depth));
+        }
+        if data.len() != width * height {
+            return Err(format!(
+                "Chunky data is {} bytes, expected {} for {}x{}",
+                data.len(),
+                width * height,
+                width,
+                height
+            ));
+        }
+
+        let stride = ((width + 15) >> 3) & !1_usize;
+        let mut planes = vec![vec![0u8; stride * height]; depth];
+        for (ii, &index) in data.iter().enumerate() {
+            let (xx, yy) = (ii % width, ii / width);
+            let byte_index = yy * stride + (xx >> 3);
+            let mask = 0x80u8 >> (xx & 0x07);
+            for (pp, plane) in planes.iter_mut().enumerate() {
+                if index & (1 << pp) != 0 {
+                    plane[byte_index] |= mask;
+                }
+            }
+        }
+        Ok(BitMap::from_planes(planes, width, height, depth, stride))
+    }
+
     /**
      * Convert a BitMap into an RGB32 pixel buffer using the provided color palette.
      * If set, the key_color index in the palette will be treated as transparent
@@ -190,6 +777,8 @@ impl BitMap {
         colors: &Palette,
         key_color: Option<usize>,
     ) -> Result<(Vec<u8>, usize), String> {
+        self.validate()?;
+
         // start with a clear pixel buffer
         let pixel_count = self.width * self.height;
         let mut pixels: Vec<u8> = Vec::with_capacity(pixel_count * 4);
@@ -199,12 +788,67 @@ impl BitMap {
         Ok((pixels, self.width * 4))
     }
 
+    /**
+     * Convert a BitMap into an owned SDL Surface using the provided color palette,
+     * for cursors and software blits. Pixels are laid out as by `generate_rgb32`
+     * (R, G, B, A bytes), with key_color treated as transparent.
+     */
+    pub fn to_surface(
+        &self,
+        colors: &Palette,
+        key_color: Option<usize>,
+    ) -> Result<Surface<'static>, String> {
+        let (pixels, stride) = self.generate_rgb32(colors, key_color)?;
+        let mut surface = Surface::new(self.width as u32, self.height as u32, PixelFormat::RGBA32)
+            .map_err(|e| e.to_string())?;
+
+        // the surface pitch may be padded, so copy row by row
+        let pitch = surface.pitch() as usize;
+        surface.with_lock_mut(|dest| {
+            for (row, src) in pixels.chunks_exact(stride).enumerate() {
+                dest[row * pitch..row * pitch + stride].copy_from_slice(src);
+            }
+        });
+        Ok(surface)
+    }
+
+    /**
+     * Like `generate_rgb32`, but every palette color passes through `f` as
+     * `f(index, base_rgba)` and the returned 0xRRGGBBAA value is used instead,
+     * for tinting or replacing colors per index. A key color reaches `f` as
+     * transparent black.
+     */
+    pub fn generate_rgb32_with<F: Fn(usize, u32) -> u32>(
+        &self,
+        colors: &Palette,
+        key_color: Option<usize>,
+        f: F,
+    ) -> Result<(Vec<u8>, usize), String> {
+        self.validate()?;
+
+        let mut pixels: Vec<u8> = vec![0; self.width * self.height * 4];
+        self.update_rgb32_mapped(&mut pixels, self.width * 4, colors, key_color, f)?;
+        Ok((pixels, self.width * 4))
+    }
+
     pub fn update_rgb32(
         &self,
         pixels: &mut Vec<u8>,
         stride: usize,
         colors: &Palette,
         key_color: Option<usize>,
+    ) -> Result<(), String> {
+        self.update_rgb32_mapped(pixels, stride, colors, key_color, |_, color| color)
+    }
+
+    // update_rgb32 with each color table entry passed through `f`
+    fn update_rgb32_mapped<F: Fn(usize, u32) -> u32>(
+        &self,
+        pixels: &mut [u8],
+        stride: usize,
+        colors: &Palette,
+        key_color: Option<usize>,
+        f: F,
     ) -> Result<(), String> {
         let pixel_count = self.width * self.height;
         if pixels.len() < pixel_count * 4 {
@@ -219,31 +863,12 @@ impl BitMap {
                 color_table[key_index] = 0x00000000;
             }
         }
-
-        // optimization: reverse iterate over the planes and build an index buffer directly from plane data
-        if self.index_buffer.borrow().is_none() {
-            // build index buffer
-            let mut index_buffer: Vec<usize> = Vec::with_capacity(self.width * self.height);
-            for yy in 0..self.height {
-                for xx in 0..self.width {
-                    let mut pixel_index: usize = 0;
-                    for pp in 0..self.depth {
-                        let plane = &self.planes[pp];
-                        let byte_index = yy * self.stride + (xx >> 3);
-                        let bit_index = 7 - (xx & 0x07);
-                        let bit = (plane[byte_index] >> bit_index) & 0x01;
-                        pixel_index |= (bit as usize) << pp;
-                    }
-                    index_buffer.push(pixel_index);
-                }
-            }
-            // cache it
-            *self.index_buffer.borrow_mut() = Some(index_buffer);
+        for (index, color) in color_table.iter_mut().enumerate() {
+            *color = f(index, *color);
         }
 
         // now build the pixel buffer from the index buffer and color table
-        let index_buffer = self.index_buffer.borrow();
-        let indices = index_buffer.as_ref().unwrap();
+        let indices = self.index_buffer();
 
         // since stride may not match (esp if we're copying into a larger pixmap), we have to write row by row
         for row in 0..self.height {
@@ -252,11 +877,54 @@ impl BitMap {
             for col in 0..self.width {
                 let color_index = indices[row_start + col];
                 let color = color_table[color_index];
-                let pixel_offset = pixel_row_start + col * 4;
-                pixels[pixel_offset + 0] = ((color >> 24) & 0xFF) as u8; // R
-                pixels[pixel_offset + 1] = ((color >> 16) & 0xFF) as u8; // G
-                pixels[pixel_offset + 2] = ((color >> 8) & 0xFF) as u8; // B
-                pixels[pixel_offset + 3] = (color & 0xFF) as u8; // A
+                put_rgba(pixels, pixel_row_start + col * 4, color);
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Like `update_rgb32`, but only rewrites pixels whose palette index is in
+     * `changed_indices`, leaving the rest of the buffer as it was. Meant for
+     * color cycling, where a few palette entries change every frame. The key
+     * color stays transparent black, as in `update_rgb32`.
+     */
+    pub fn update_rgb32_range(
+        &self,
+        pixels: &mut [u8],
+        stride: usize,
+        colors: &Palette,
+        key_color: Option<usize>,
+        changed_indices: &[usize],
+    ) -> Result<(), String> {
+        let pixel_count = self.width * self.height;
+        if pixels.len() < pixel_count * 4 {
+            return Err("Provided pixel buffer is too small for BitMap dimensions".to_string());
+        }
+
+        let mut color_table = colors.to_rgba32_table(self.depth)?;
+        if let Some(key_index) = key_color {
+            if key_index < color_table.len() {
+                color_table[key_index] = 0x00000000;
+            }
+        }
+        let mut changed = vec![false; color_table.len()];
+        for &index in changed_indices {
+            if let Some(flag) = changed.get_mut(index) {
+                *flag = true;
+            }
+        }
+
+        let indices = self.index_buffer();
+        for row in 0..self.height {
+            let row_start = row * self.width;
+            let pixel_row_start = row * stride;
+            for col in 0..self.width {
+                let color_index = indices[row_start + col];
+                if changed[color_index] {
+                    put_rgba(pixels, pixel_row_start + col * 4, color_table[color_index]);
+                }
             }
         }
 
@@ -264,6 +932,11 @@ impl BitMap {
     }
 }
 
+// Store a packed 0xRRGGBBAA color as R, G, B, A bytes at `offset`.
+fn put_rgba(pixels: &mut [u8], offset: usize, color: u32) {
+    pixels[offset..offset + 4].copy_from_slice(&color.to_be_bytes());
+}
+
 #[cfg(test)]
 mod tests {
     use crate::game::colors::RGB4;
@@ -322,6 +995,193 @@ mod tests {
         BitMap::with_data(data, width, height, depth, stride)
     }
 
+    #[test]
+    fn test_content_hash_detects_single_bit_change() {
+        let bitmap = build_test_bitmap();
+        let same = build_test_bitmap();
+        // the lazily built index cache doesn't affect the hash
+        let _ = same.indices().count();
+        assert_eq!(bitmap.content_hash(), same.content_hash());
+        assert_eq!(bitmap.content_hash(), bitmap.clone().content_hash());
+
+        let mut changed = build_test_bitmap();
+        changed.set_pixel(5, 9, changed.get_pixel(5, 9) ^ 1);
+        assert_ne!(bitmap.content_hash(), changed.content_hash());
+
+        // same bytes, different shape
+        let reshaped = BitMap::from_planes(bitmap.planes.clone(), 8, 32, 2, 1);
+        assert_ne!(bitmap.content_hash(), reshaped.content_hash());
+    }
+
+    #[test]
+    fn test_to_depth_adds_zero_planes() {
+        let bitmap = build_test_bitmap();
+        let deeper = bitmap.to_depth(4).unwrap();
+        assert_eq!((deeper.depth, deeper.stride), (4, bitmap.stride));
+        assert_eq!(deeper.planes.len(), 4);
+        assert!(deeper.planes[2..].iter().flatten().all(|&byte| byte == 0));
+        assert!(bitmap.indices().eq(deeper.indices()));
+
+        assert!(bitmap.to_depth(1).is_err());
+        assert_eq!(bitmap.to_depth(2).unwrap().planes, bitmap.planes);
+    }
+
+    #[test]
+    fn test_histogram_counts_checkerboard() {
+        let bitmap = build_test_bitmap();
+        // half the pixels use index 2, the rest index 0
+        assert_eq!(bitmap.histogram(), vec![128, 0, 128, 0]);
+    }
+
+    #[test]
+    fn test_resize_nearest_up_and_down() {
+        let bitmap = build_test_bitmap();
+
+        let up = bitmap.resize_nearest(24, 24).unwrap();
+        assert_eq!((up.width, up.height, up.depth, up.stride), (24, 24, 2, 4));
+        // target 1 samples source 0, target 2 samples source 1, target 3 samples 2
+        assert_eq!(up.get_pixel(1, 0), bitmap.get_pixel(0, 0));
+        assert_eq!(up.get_pixel(2, 0), bitmap.get_pixel(1, 0));
+        assert_eq!(up.get_pixel(3, 3), bitmap.get_pixel(2, 2));
+        assert_eq!(up.get_pixel(23, 22), bitmap.get_pixel(15, 14));
+
+        let down = bitmap.resize_nearest(8, 8).unwrap();
+        assert_eq!((down.width, down.height, down.stride), (8, 8, 2));
+        // every other source pixel lands on the same checkerboard color
+        assert_eq!(down.histogram(), vec![0, 0, 64, 0]);
+        assert_eq!(down.get_pixel(3, 5), bitmap.get_pixel(6, 10));
+
+        assert!(bitmap.resize_nearest(0, 8).is_err());
+    }
+
+    #[test]
+    fn test_shadow_offsets_silhouette() {
+        // 3x2 sprite on index 0 with an L shape in indices 1 and 2
+        let mut sprite = BitMap::build(3, 2, 2).unwrap();
+        sprite.set_pixel(0, 0, 1);
+        sprite.set_pixel(0, 1, 2);
+        sprite.set_pixel(1, 1, 1);
+
+        let shadow = sprite.shadow(0, (2, 1), 3);
+        assert_eq!((shadow.width, shadow.height), (5, 3));
+        let mut cast: Vec<(usize, usize)> = shadow
+            .indices()
+            .filter(|&(_, _, index)| index == 3)
+            .map(|(xx, yy, _)| (xx, yy))
+            .collect();
+        cast.sort();
+        assert_eq!(cast, vec![(2, 1), (2, 2), (3, 2)]);
+        assert_eq!(shadow.histogram(), vec![12, 0, 0, 3]);
+
+        // a negative offset moves the sprite instead, the shadow stays at 0, 0
+        let shadow = sprite.shadow(0, (-1, -1), 3);
+        assert_eq!((shadow.width, shadow.height), (4, 3));
+        assert_eq!(shadow.get_pixel(0, 0), 3);
+        assert_eq!(shadow.get_pixel(1, 1), 3);
+        assert_eq!(shadow.get_pixel(1, 0), 0);
+    }
+
+    #[test]
+    fn test_chunky_round_trip() {
+        let bitmap = build_test_bitmap();
+        let chunky = bitmap.to_chunky();
+        assert_eq!(chunky.len(), 16 * 16);
+        assert_eq!(&chunky[..3], &[2, 0, 2]);
+
+        let restored = BitMap::from_chunky(&chunky, 16, 16, 2).unwrap();
+        assert_eq!(restored.stride, bitmap.stride);
+        assert_eq!(restored.planes, bitmap.planes);
+
+        assert!(BitMap::from_chunky(&chunky, 16, 15, 2).is_err());
+        assert!(BitMap::from_chunky(&chunky, 16, 16, 9).is_err());
+    }
+
+    #[test]
+    fn test_indices_iterates_checkerboard() {
+        let bitmap = build_test_bitmap();
+        let mut count = 0;
+        for (xx, yy, index) in bitmap.indices() {
+            let expected = if (xx + yy) % 2 == 0 { 2 } else { 0 };
+            assert_eq!(index, expected, "pixel ({xx}, {yy})");
+            assert_eq!(index, bitmap.get_pixel(xx, yy));
+            count += 1;
+        }
+        assert_eq!(count, 16 * 16);
+
+        let first: Vec<(usize, usize, usize)> = bitmap.indices().take(3).collect();
+        assert_eq!(first, vec![(0, 0, 2), (1, 0, 0), (2, 0, 2)]);
+    }
+
+    #[test]
+    fn test_scroll_horizontal() {
+        let mut wrapped = build_test_bitmap();
+        wrapped.scroll(1, 0, true);
+        for (xx, yy, index) in wrapped.indices() {
+            // every pixel, including the wrapped column 0, moved right by one
+            let expected = if (xx + 15 + yy) % 2 == 0 { 2 } else { 0 };
+            assert_eq!(index, expected, "pixel ({xx}, {yy})");
+        }
+        assert_eq!(wrapped.get_pixel(0, 1), 2);
+
+        let mut clipped = build_test_bitmap();
+        clipped.scroll(1, 0, false);
+        for (xx, yy, index) in clipped.indices() {
+            let expected = if xx > 0 && (xx - 1 + yy) % 2 == 0 {
+                2
+            } else {
+                0
+            };
+            assert_eq!(index, expected, "pixel ({xx}, {yy})");
+        }
+    }
+
+    #[test]
+    fn test_scroll_vertical() {
+        let mut bitmap = build_test_bitmap();
+        bitmap.scroll(0, 1, false);
+        for (xx, yy, index) in bitmap.indices() {
+            let expected = if yy > 0 && (xx + yy - 1) % 2 == 0 {
+                2
+            } else {
+                0
+            };
+            assert_eq!(index, expected, "pixel ({xx}, {yy})");
+        }
+
+        // scrolling a full height with wrap is a no-op
+        let mut bitmap = build_test_bitmap();
+        bitmap.scroll(0, -16, true);
+        for (xx, yy, index) in bitmap.indices() {
+            assert_eq!(index, build_test_bitmap().get_pixel(xx, yy));
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(build_test_bitmap().validate().is_ok());
+        assert!(BitMap::build(17, 3, 5).unwrap().validate().is_ok());
+
+        let mut short_plane = build_test_bitmap();
+        short_plane.planes[1].pop();
+        let err = short_plane.validate().unwrap_err();
+        assert!(err.contains("plane 1 is 31 bytes, expected 32"), "{err}");
+        assert!(short_plane
+            .generate_rgb32(&Palette { colors: Vec::new() }, None)
+            .is_err());
+
+        let mut missing_plane = build_test_bitmap();
+        missing_plane.planes.pop();
+        let err = missing_plane.validate().unwrap_err();
+        assert!(err.contains("1 planes but depth 2"), "{err}");
+
+        let mut narrow = build_test_bitmap();
+        narrow.width = 17;
+        assert!(narrow
+            .validate()
+            .unwrap_err()
+            .contains("too small for width 17"));
+    }
+
     #[test]
     fn test_bitmap_creation() {
         let bitmap = BitMap::build(320, 200, 5).unwrap();
@@ -421,6 +1281,295 @@ mod tests {
         assert_eq!(pixels[7], 0xFF); // A
     }
 
+    #[test]
+    fn test_content_bounds_and_cropped() {
+        // 4x3 block of color 3 at (5, 6) on a 16x16 background of color 0
+        let mut bitmap = BitMap::build(16, 16, 2).unwrap();
+        for yy in 6..9 {
+            for xx in 5..9 {
+                bitmap.set_pixel(xx, yy, 3);
+            }
+        }
+        bitmap.set_pixel(6, 7, 1); // interior detail must survive the crop
+
+        let bounds = bitmap.content_bounds(0).unwrap();
+        assert_eq!(
+            (bounds.x(), bounds.y(), bounds.width(), bounds.height()),
+            (5, 6, 4, 3)
+        );
+
+        let cropped = bitmap.cropped(0).unwrap();
+        assert_eq!(cropped.get_size(), (4, 3));
+        assert_eq!(cropped.depth, 2);
+        assert_eq!(cropped.get_pixel(0, 0), 3);
+        assert_eq!(cropped.get_pixel(1, 1), 1);
+        assert_eq!(cropped.get_pixel(3, 2), 3);
+    }
+
+    #[test]
+    fn test_draw_text_sets_glyph_pixels() {
+        // monospace 4x3 font with just 'I', drawn as a single centre column
+        let mut font = DiskFont::new();
+        font.y_size = 3;
+        font.x_size = 4;
+        font.lo_char = b'I';
+        font.hi_char = b'I';
+        font.modulo = 4;
+        font.char_data = vec![0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0];
+        font.char_loc = vec![(0, 4)];
+
+        let mut bitmap = BitMap::build(8, 4, 2).unwrap();
+        bitmap.draw_text(&font, "II?", 2, 1, 3);
+
+        for yy in 0..4 {
+            for xx in 0..8 {
+                let expected = if (1..4).contains(&yy) && (xx == 3 || xx == 7) {
+                    3
+                } else {
+                    0
+                };
+                assert_eq!(bitmap.get_pixel(xx, yy), expected, "pixel ({xx}, {yy})");
+            }
+        }
+
+        // glyphs past the right/bottom edges are clipped rather than panicking
+        bitmap.draw_text(&font, "III", 6, 2, 1);
+        assert_eq!(bitmap.get_pixel(7, 3), 1);
+    }
+
+    #[test]
+    fn test_to_surface_matches_rgb32() {
+        let bitmap = build_test_bitmap();
+        let palette = Palette {
+            colors: vec![
+                RGB4::from(0x000),
+                RGB4::from(0xF00),
+                RGB4::from(0x0F0),
+                RGB4::from(0x00F),
+            ],
+        };
+        let (pixels, stride) = bitmap.generate_rgb32(&palette, None).unwrap();
+
+        let surface = bitmap.to_surface(&palette, None).unwrap();
+        assert_eq!(surface.width(), 16);
+        assert_eq!(surface.height(), 16);
+
+        let pitch = surface.pitch() as usize;
+        surface.with_lock(|data| {
+            for (xx, yy) in [(0, 0), (1, 0), (5, 3)] {
+                let src = yy * stride + xx * 4;
+                let dst = yy * pitch + xx * 4;
+                assert_eq!(data[dst..dst + 4], pixels[src..src + 4]);
+            }
+        });
+    }
+
+    #[test]
+    fn test_update_rgb32_range_only_touches_changed_index() {
+        let mut bitmap = BitMap::build(16, 4, 3).unwrap();
+        for yy in 0..4 {
+            for xx in 0..16 {
+                bitmap.set_pixel(xx, yy, if xx % 3 == 0 { 5 } else { xx % 5 });
+            }
+        }
+        let mut palette = Palette { colors: Vec::new() };
+        for cc in 0..8 {
+            palette.colors.push(RGB4::from(0x111 * cc as u16));
+        }
+        let (mut pixels, stride) = bitmap.generate_rgb32(&palette, None).unwrap();
+        let previous = pixels.clone();
+
+        palette.colors[5] = RGB4::from(0xF0F);
+        palette.colors[2] = RGB4::from(0x0F0); // changed but not listed
+        bitmap
+            .update_rgb32_range(&mut pixels, stride, &palette, None, &[5])
+            .unwrap();
+
+        for (xx, yy, index) in bitmap.indices() {
+            let offset = yy * stride + xx * 4;
+            if index == 5 {
+                assert_eq!(pixels[offset..offset + 4], [0xFF, 0x00, 0xFF, 0xFF]);
+            } else {
+                assert_eq!(pixels[offset..offset + 4], previous[offset..offset + 4]);
+            }
+        }
+
+        // a changed key color is rewritten as transparent black
+        bitmap
+            .update_rgb32_range(&mut pixels, stride, &palette, Some(5), &[5, 2])
+            .unwrap();
+        for (xx, yy, index) in bitmap.indices() {
+            let offset = yy * stride + xx * 4;
+            match index {
+                5 => assert_eq!(pixels[offset..offset + 4], [0, 0, 0, 0]),
+                2 => assert_eq!(pixels[offset..offset + 4], [0x00, 0xFF, 0x00, 0xFF]),
+                _ => assert_eq!(pixels[offset..offset + 4], previous[offset..offset + 4]),
+            }
+        }
+    }
+
+    #[test]
+    fn test_edge_map_filled_rectangle() {
+        // 4x3 rectangle of index 3 at (2, 1), plus a 2x2 block touching the
+        // bottom-right corner of the bitmap
+        let mut bitmap = BitMap::build(16, 6, 2).unwrap();
+        for yy in 1..4 {
+            for xx in 2..6 {
+                bitmap.set_pixel(xx, yy, 3);
+            }
+        }
+        for yy in 4..6 {
+            for xx in 14..16 {
+                bitmap.set_pixel(xx, yy, 1);
+            }
+        }
+
+        let edges = bitmap.edge_map(0);
+        assert_eq!(edges.depth, 2);
+        let rows: Vec<Vec<usize>> = (0..5)
+            .map(|yy| (1..7).map(|xx| edges.get_pixel(xx, yy)).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![0, 0, 0, 0, 0, 0],
+                vec![0, 2, 2, 2, 2, 0],
+                vec![0, 2, 1, 1, 3, 0],
+                vec![0, 2, 3, 3, 3, 0],
+                vec![0, 0, 0, 0, 0, 0],
+            ]
+        );
+
+        // off-bitmap pixels are background: the bottom-right corner block
+        // still has shadow edges along the bitmap border
+        assert_eq!(edges.get_pixel(14, 4), EDGE_HIGHLIGHT);
+        assert_eq!(edges.get_pixel(15, 5), EDGE_SHADOW);
+    }
+
+    #[test]
+    fn test_generate_rgb32_with_callback() {
+        let bitmap = build_test_bitmap();
+        let mut palette = Palette { colors: Vec::new() };
+        palette.colors.push(RGB4::from(0x006));
+        palette.colors.push(RGB4::from(0xFFF));
+        palette.colors.push(RGB4::from(0x390));
+        palette.colors.push(RGB4::from(0x000));
+
+        let (plain, _) = bitmap.generate_rgb32(&palette, None).unwrap();
+        let (pixels, stride) = bitmap
+            .generate_rgb32_with(&palette, None, |_, color| (color & 0xFFFFFF00) | 0x80)
+            .unwrap();
+        assert_eq!(stride, 64);
+        for (tinted, base) in pixels.chunks_exact(4).zip(plain.chunks_exact(4)) {
+            assert_eq!(tinted[..3], base[..3]);
+            assert_eq!(tinted[3], 0x80);
+        }
+
+        // the callback sees the palette index
+        let (pixels, _) = bitmap
+            .generate_rgb32_with(
+                &palette,
+                None,
+                |index, color| if index == 2 { 0x11223344 } else { color },
+            )
+            .unwrap();
+        assert_eq!(pixels[0..4], [0x11, 0x22, 0x33, 0x44]);
+        assert_eq!(pixels[4..8], plain[4..8]);
+    }
+
+    #[test]
+    fn test_from_channels_assembles_rgb() {
+        let channel = |depth: usize, value: usize| {
+            let mut bitmap = BitMap::from_planes(vec![vec![0; 2 * 2]; depth], 10, 2, depth, 2);
+            bitmap.set_pixel(3, 1, value);
+            bitmap
+        };
+        let deep =
+            BitMap::from_channels(&channel(8, 0x12), &channel(8, 0xAB), &channel(4, 0x7)).unwrap();
+        assert_eq!(deep.depth, 24);
+        assert!(deep.validate().is_ok());
+        // the 4-plane blue channel lands in the high nibble
+        assert_eq!(deep.get_pixel(3, 1), 0x70AB12);
+        assert_eq!(deep.get_pixel(2, 1), 0);
+
+        let narrow = BitMap::from_planes(vec![vec![0; 2]; 8], 10, 1, 8, 2);
+        assert!(BitMap::from_channels(&channel(8, 1), &narrow, &channel(8, 1)).is_err());
+    }
+
+    #[test]
+    fn test_blend_masked_half_and_half() {
+        let mut first = BitMap::build(16, 4, 3).unwrap();
+        let mut second = BitMap::build(16, 4, 3).unwrap();
+        for yy in 0..4 {
+            for xx in 0..16 {
+                first.set_pixel(xx, yy, 3);
+                second.set_pixel(xx, yy, 5);
+            }
+        }
+
+        // left half from first, right half from second
+        let mask: Vec<u8> = (0..first.stride * first.height)
+            .map(|i| if i % first.stride == 0 { 0x00 } else { 0xFF })
+            .collect();
+        let blended = first.blend_masked(&second, &mask).unwrap();
+
+        for yy in 0..4 {
+            for xx in 0..16 {
+                let expected = if xx < 8 { 3 } else { 5 };
+                assert_eq!(
+                    blended.get_pixel(xx, yy),
+                    expected,
+                    "pixel ({}, {})",
+                    xx,
+                    yy
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_stencil_fills_outside() {
+        let mut bitmap = BitMap::build(16, 4, 3).unwrap();
+        for yy in 0..4 {
+            for xx in 0..16 {
+                bitmap.set_pixel(xx, yy, 3);
+            }
+        }
+        // warm the index cache so a stale cache would show up
+        assert_eq!(bitmap.indices().next(), Some((0, 0, 3)));
+
+        // keep the top two rows, fill the bottom two with index 6
+        let stencil: Vec<u8> = (0..bitmap.stride * bitmap.height)
+            .map(|i| if i < bitmap.stride * 2 { 0xFF } else { 0x00 })
+            .collect();
+        bitmap.apply_stencil(&stencil, 6).unwrap();
+
+        for (xx, yy, index) in bitmap.indices() {
+            let expected = if yy < 2 { 3 } else { 6 };
+            assert_eq!(index, expected, "pixel ({}, {})", xx, yy);
+        }
+
+        // a short stencil is rejected without touching the pixels
+        assert!(bitmap.apply_stencil(&stencil[1..], 0).is_err());
+        assert_eq!(bitmap.get_pixel(0, 3), 6);
+    }
+
+    #[test]
+    fn test_blend_masked_depth_mismatch() {
+        let first = BitMap::build(16, 4, 3).unwrap();
+        let second = BitMap::build(16, 4, 2).unwrap();
+        assert!(first.blend_masked(&second, &[0; 8]).is_err());
+        // a mask that isn't one plane in size is rejected too
+        assert!(first.blend_masked(&first, &[0; 7]).is_err());
+    }
+
+    #[test]
+    fn test_content_bounds_all_background() {
+        let bitmap = BitMap::build(16, 4, 2).unwrap();
+        assert!(bitmap.content_bounds(0).is_none());
+        assert!(bitmap.cropped(0).is_none());
+    }
+
     #[test]
     fn test_generate_rgb32_interleaved() {
         let bitmap = build_interleaved_test_bitmap();
@@ -30,13 +30,69 @@ pub fn read_u8(data: &Vec<u8>, offset: &mut usize) -> u8 {
     cc
 }
 
+// Fixed-point values, big-endian. 16.16 is a LONG with 16 fraction bits,
+// 8.8 a WORD with 8. Writers round to the nearest step and saturate at the
+// type's range.
+
+const FIXED16_16_ONE: f64 = 65536.0;
+const FIXED8_8_ONE: f64 = 256.0;
+
+pub fn read_fixed16_16(data: &[u8], offset: &mut usize) -> f64 {
+    let vs = &data[*offset..*offset + 4];
+    *offset += 4;
+    i32::from_be_bytes(vs.try_into().unwrap()) as f64 / FIXED16_16_ONE
+}
+
+pub fn read_ufixed16_16(data: &[u8], offset: &mut usize) -> f64 {
+    let vs = &data[*offset..*offset + 4];
+    *offset += 4;
+    u32::from_be_bytes(vs.try_into().unwrap()) as f64 / FIXED16_16_ONE
+}
+
+pub fn read_fixed8_8(data: &[u8], offset: &mut usize) -> f64 {
+    let vs = &data[*offset..*offset + 2];
+    *offset += 2;
+    i16::from_be_bytes(vs.try_into().unwrap()) as f64 / FIXED8_8_ONE
+}
+
+pub fn read_ufixed8_8(data: &[u8], offset: &mut usize) -> f64 {
+    let vs = &data[*offset..*offset + 2];
+    *offset += 2;
+    u16::from_be_bytes(vs.try_into().unwrap()) as f64 / FIXED8_8_ONE
+}
+
+pub fn write_fixed16_16(data: &mut [u8], offset: &mut usize, value: f64) {
+    let raw = (value * FIXED16_16_ONE).round() as i32;
+    data[*offset..*offset + 4].copy_from_slice(&raw.to_be_bytes());
+    *offset += 4;
+}
+
+pub fn write_ufixed16_16(data: &mut [u8], offset: &mut usize, value: f64) {
+    let raw = (value * FIXED16_16_ONE).round() as u32;
+    data[*offset..*offset + 4].copy_from_slice(&raw.to_be_bytes());
+    *offset += 4;
+}
+
+pub fn write_fixed8_8(data: &mut [u8], offset: &mut usize, value: f64) {
+    let raw = (value * FIXED8_8_ONE).round() as i16;
+    data[*offset..*offset + 2].copy_from_slice(&raw.to_be_bytes());
+    *offset += 2;
+}
+
+pub fn write_ufixed8_8(data: &mut [u8], offset: &mut usize, value: f64) {
+    let raw = (value * FIXED8_8_ONE).round() as u16;
+    data[*offset..*offset + 2].copy_from_slice(&raw.to_be_bytes());
+    *offset += 2;
+}
+
 // This only reads up to the FIRST NUL byte
 // it is up to the caller to pad any remaining bytes
+// An unterminated string runs to the end of the data
 pub fn read_string(data: &Vec<u8>, offset: &mut usize) -> String {
-    let str_start = *offset;
-    let mut str_end = *offset + 1;
+    let str_start = (*offset).min(data.len());
+    let mut str_end = (*offset + 1).min(data.len());
 
-    while data[str_end] != 0 {
+    while str_end < data.len() && data[str_end] != 0 {
         str_end += 1;
     }
 
@@ -48,7 +104,7 @@ pub fn read_string(data: &Vec<u8>, offset: &mut usize) -> String {
 
     // for some reason passing just a NUL character to from_ut8 results in "\0"
     // instead of an empty string, IMHO this is a bug in Rust
-    if str_end - str_start == 1 {
+    if str_end - str_start == 1 && data[str_start] == 0 {
         return "".to_string();
     }
 
@@ -71,3 +127,123 @@ pub fn try_read_u32(data: &[u8], offset: &mut usize) -> Result<u32, String> {
     *offset += 4;
     Ok(u32::from_be_bytes(vs.try_into().unwrap()))
 }
+
+pub fn try_read_u16(data: &[u8], offset: &mut usize) -> Result<u16, String> {
+    if *offset + 2 > data.len() {
+        return Err(format!(
+            "read_u16: offset {} + 2 exceeds length {}",
+            *offset,
+            data.len()
+        ));
+    }
+    let vs = &data[*offset..*offset + 2];
+    *offset += 2;
+    Ok(u16::from_be_bytes(vs.try_into().unwrap()))
+}
+
+pub fn try_read_u8(data: &[u8], offset: &mut usize) -> Result<u8, String> {
+    if *offset + 1 > data.len() {
+        return Err(format!(
+            "read_u8: offset {} + 1 exceeds length {}",
+            *offset,
+            data.len()
+        ));
+    }
+    let v = data[*offset];
+    *offset += 1;
+    Ok(v)
+}
+
+pub fn try_read_bytes<'a>(
+    data: &'a [u8],
+    offset: &mut usize,
+    len: usize,
+) -> Result<&'a [u8], String> {
+    if *offset + len > data.len() {
+        return Err(format!(
+            "read_bytes: offset {} + {} exceeds length {}",
+            *offset,
+            len,
+            data.len()
+        ));
+    }
+    let vs = &data[*offset..*offset + len];
+    *offset += len;
+    Ok(vs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_string_terminated() {
+        let data = b"topaz\0rest".to_vec();
+        let mut offset = 0;
+        assert_eq!(read_string(&data, &mut offset), "topaz");
+        assert_eq!(offset, 5);
+    }
+
+    #[test]
+    fn test_read_string_unterminated_tail() {
+        let data = b"\0\0name".to_vec();
+        let mut offset = 2;
+        assert_eq!(read_string(&data, &mut offset), "name");
+        assert_eq!(offset, data.len());
+
+        // a single character tail is kept
+        let mut offset = 5;
+        assert_eq!(read_string(&data, &mut offset), "e");
+
+        // already at the end
+        assert_eq!(read_string(&data, &mut offset), "");
+        assert_eq!(offset, data.len());
+    }
+
+    #[test]
+    fn test_read_fixed_point() {
+        let data = vec![
+            0x00, 0x01, 0x80, 0x00, // 1.5
+            0xFF, 0xFE, 0x40, 0x00, // -1.75 signed, 65534.25 unsigned
+            0x02, 0x40, // 2.25
+            0xFF, 0x80, // -0.5 signed, 255.5 unsigned
+        ];
+        let mut offset = 0;
+        assert_eq!(read_fixed16_16(&data, &mut offset), 1.5);
+        assert_eq!(read_fixed16_16(&data, &mut offset), -1.75);
+        assert_eq!(read_fixed8_8(&data, &mut offset), 2.25);
+        assert_eq!(read_fixed8_8(&data, &mut offset), -0.5);
+        assert_eq!(offset, data.len());
+
+        let mut offset = 4;
+        assert_eq!(read_ufixed16_16(&data, &mut offset), 65534.25);
+        let mut offset = 10;
+        assert_eq!(read_ufixed8_8(&data, &mut offset), 255.5);
+    }
+
+    #[test]
+    fn test_write_fixed_point_round_trip() {
+        let mut data = vec![0u8; 12];
+        let mut offset = 0;
+        write_fixed16_16(&mut data, &mut offset, -3.125);
+        write_ufixed16_16(&mut data, &mut offset, 40000.5);
+        write_fixed8_8(&mut data, &mut offset, 100.75);
+        write_ufixed8_8(&mut data, &mut offset, 0.00390625);
+        assert_eq!(offset, data.len());
+        assert_eq!(&data[0..4], &[0xFF, 0xFC, 0xE0, 0x00]);
+
+        let mut offset = 0;
+        assert_eq!(read_fixed16_16(&data, &mut offset), -3.125);
+        assert_eq!(read_ufixed16_16(&data, &mut offset), 40000.5);
+        assert_eq!(read_fixed8_8(&data, &mut offset), 100.75);
+        assert_eq!(read_ufixed8_8(&data, &mut offset), 0.00390625);
+
+        // rounds to the nearest step and saturates
+        let mut offset = 0;
+        write_fixed8_8(&mut data, &mut offset, 1000.0);
+        write_fixed8_8(&mut data, &mut offset, 0.3);
+        let mut offset = 0;
+        assert_eq!(read_fixed8_8(&data, &mut offset), i16::MAX as f64 / 256.0);
+        assert_eq!(read_fixed8_8(&data, &mut offset), 77.0 / 256.0);
+    }
+}
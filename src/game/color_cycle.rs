@@ -0,0 +1,154 @@
+use crate::game::colors::{ColorRange, Palette};
+use crate::game::image_texture::ImageTexture;
+use crate::game::render_task::RenderTask;
+
+use sdl3::rect::Rect;
+use sdl3::render::Canvas;
+use sdl3::video::Window;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/*
+ * Color cycling render task. Each ColorRange rotates its span of a shared
+ * palette at its own CRNG rate; whenever any range moves, the image is
+ * rebuilt from the palette and re-uploaded. Runs until cancelled.
+ */
+
+// CRNG rate for one step per tick (ticks are 1/60 second)
+const RATE_PER_STEP: u64 = 16384;
+
+pub struct ColorCycleTask<'tex> {
+    palette: Rc<RefCell<Palette>>,
+    ranges: Vec<ColorRange>,
+    texture: ImageTexture<'tex>,
+    key_color: Option<usize>,
+
+    // rate accumulated toward the next step, per range
+    accumulators: Vec<u64>,
+    // number of times the texture has been rebuilt
+    uploads: usize,
+}
+
+impl<'tex> ColorCycleTask<'tex> {
+    pub fn new(
+        palette: Rc<RefCell<Palette>>,
+        ranges: Vec<ColorRange>,
+        texture: ImageTexture<'tex>,
+        key_color: Option<usize>,
+    ) -> ColorCycleTask<'tex> {
+        let accumulators = vec![0; ranges.len()];
+        ColorCycleTask {
+            palette,
+            ranges,
+            texture,
+            key_color,
+            accumulators,
+            uploads: 0,
+        }
+    }
+
+    pub fn uploads(&self) -> usize {
+        self.uploads
+    }
+
+    pub fn texture(&self) -> &ImageTexture<'tex> {
+        &self.texture
+    }
+
+    /// Advance every range by `delta_ticks`, rotating the shared palette as
+    /// steps come due, and re-upload the texture if anything moved.
+    pub fn step(&mut self, delta_ticks: i32) {
+        let delta_ticks = delta_ticks.max(0) as u64;
+        let mut changed = false;
+        {
+            let mut palette = self.palette.borrow_mut();
+            for (range, accumulator) in self.ranges.iter().zip(self.accumulators.iter_mut()) {
+                *accumulator += range.rate as u64 * delta_ticks;
+                let steps = *accumulator / RATE_PER_STEP;
+                *accumulator %= RATE_PER_STEP;
+
+                // whole turns of the range are no-ops, e.g. after a long pause
+                let high = range.high.min(palette.colors.len().saturating_sub(1));
+                let len = (high + 1).saturating_sub(range.low) as u64;
+                if steps == 0 || len < 2 {
+                    continue;
+                }
+                for _ in 0..steps % len {
+                    palette.cycle_range(range.low, range.high, range.reverse);
+                }
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.texture.update(&self.palette.borrow(), self.key_color);
+            self.uploads += 1;
+        }
+    }
+}
+
+impl RenderTask for ColorCycleTask<'_> {
+    fn update(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        delta_ticks: i32,
+        _area: Option<Rect>,
+    ) -> bool {
+        self.step(delta_ticks);
+        let bounds = self.texture.get_bounds();
+        self.texture.draw(canvas, bounds.x(), bounds.y());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::colors::RGB4;
+
+    use std::rc::Weak;
+
+    #[test]
+    fn test_ranges_rotate_and_reupload() {
+        let palette = Rc::new(RefCell::new(Palette {
+            colors: (0..8u16).map(RGB4::from).collect(),
+        }));
+        let ranges = vec![
+            // one step every tick
+            ColorRange {
+                low: 1,
+                high: 3,
+                rate: 16384,
+                reverse: false,
+            },
+            // one step every four ticks
+            ColorRange {
+                low: 4,
+                high: 7,
+                rate: 4096,
+                reverse: true,
+            },
+        ];
+        let texture = ImageTexture::placeholder(&Rect::new(0, 0, 8, 8), Weak::new());
+        let mut task = ColorCycleTask::new(palette.clone(), ranges, texture, None);
+
+        task.step(0);
+        assert_eq!(task.uploads(), 0);
+
+        for _ in 0..4 {
+            task.step(1);
+        }
+        assert_eq!(task.uploads(), 4);
+
+        let colors: Vec<u16> = palette.borrow().colors.iter().map(|c| c.color).collect();
+        // 1..=3 rotated forward four times (once around plus one), 4..=7 back once
+        assert_eq!(colors, vec![0, 3, 1, 2, 5, 6, 7, 4]);
+
+        // a huge step after a long pause doesn't overflow and only applies
+        // the net rotation: i32::MAX steps is one past a whole turn of 1..=3
+        task.step(i32::MAX);
+        let colors: Vec<u16> = palette.borrow().colors.iter().map(|c| c.color).collect();
+        assert_eq!(&colors[1..4], &[2, 3, 1]);
+    }
+}
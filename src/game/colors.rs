@@ -82,26 +82,157 @@ impl RGB4 {
         let bc = self.color & 0x0F;
         (bc | (bc << 4)) as u8
     }
+
+    /// Interpolate each 4-bit component toward `other`, rounding to the
+    /// nearest nibble. `t` is clamped to 0..=1; 0 yields `self`, 1 yields `other`.
+    pub fn blend(&self, other: &RGB4, t: f32) -> RGB4 {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |shift: u16| -> u16 {
+            let from = ((self.color >> shift) & 0xF) as f32;
+            let to = ((other.color >> shift) & 0xF) as f32;
+            ((from + (to - from) * t).round() as u16) << shift
+        };
+        RGB4 {
+            color: mix(8) | mix(4) | mix(0),
+        }
+    }
+
+    /// WCAG contrast ratio between this color and `other`, from 1 (same
+    /// luminance) to 21 (black on white). Order doesn't matter.
+    pub fn contrast_ratio(&self, other: &RGB4) -> f32 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    // WCAG relative luminance of the color expanded to 8-bit sRGB.
+    fn relative_luminance(&self) -> f32 {
+        let linear = |channel: u8| -> f32 {
+            let c = channel as f32 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r()) + 0.7152 * linear(self.g()) + 0.0722 * linear(self.b())
+    }
+}
+
+/// Alpha values below this are treated as transparent when quantizing.
+pub const QUANTIZE_ALPHA_THRESHOLD: u8 = 0x80;
+
+/// Dithering method used when quantizing an RGBA image to a palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DitherMode {
+    /// 4x4 Bayer threshold matrix.
+    Ordered,
+    /// Floyd-Steinberg error diffusion.
+    FloydSteinberg,
+}
+
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// Squared Euclidean distance between a palette color and an 8-bit RGB color.
+fn distance_sq(c: &RGB4, (r, g, b): (u8, u8, u8)) -> u32 {
+    let dr = c.r() as i32 - r as i32;
+    let dg = c.g() as i32 - g as i32;
+    let db = c.b() as i32 - b as i32;
+    (dr * dr + dg * dg + db * db) as u32
 }
 
-#[derive(Deserialize, Debug, Clone)]
+/// A span of palette entries rotated for color cycling, as described by an
+/// ILBM CRNG chunk. `rate` uses the CRNG scale, where 16384 is one step per
+/// 1/60 second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorRange {
+    pub low: usize,
+    pub high: usize,
+    pub rate: u16,
+    pub reverse: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
 pub struct Palette {
     #[serde(deserialize_with = "deserialize_rgb4_vec")]
     pub colors: Vec<RGB4>,
 }
 
 impl Palette {
+    /// Build a palette of `steps` colors evenly interpolated from `start` to
+    /// `end` (both included) with `RGB4::blend`. Zero steps gives an empty
+    /// palette and one step gives just `start`.
+    pub fn ramp(start: RGB4, end: RGB4, steps: usize) -> Palette {
+        let colors = match steps {
+            0 => Vec::new(),
+            1 => vec![start],
+            _ => (0..steps)
+                .map(|step| start.blend(&end, step as f32 / (steps - 1) as f32))
+                .collect(),
+        };
+        Palette { colors }
+    }
+
+    /// Copy of the palette with every 4-bit component multiplied by
+    /// `factor` and rounded to the nearest nibble. Negative factors give
+    /// black; components that would pass 15 saturate.
+    pub fn scaled_brightness(&self, factor: f32) -> Palette {
+        let factor = factor.max(0.0);
+        let scale = |color: u16, shift: u16| -> u16 {
+            let component = ((color >> shift) & 0xF) as f32;
+            ((component * factor).round().min(15.0) as u16) << shift
+        };
+        Palette {
+            colors: self
+                .colors
+                .iter()
+                .map(|c| RGB4 {
+                    color: scale(c.color, 8) | scale(c.color, 4) | scale(c.color, 0),
+                })
+                .collect(),
+        }
+    }
+
+    /// The palette as Amiga `COLORxx` register values (`0x0RGB`), in order.
+    pub fn to_copper_words(&self) -> Vec<u16> {
+        self.colors.iter().map(|c| c.color).collect()
+    }
+
+    /// Build a palette from `COLORxx` register values. The unused high
+    /// nibble of each word is ignored.
+    pub fn from_copper_words(words: &[u16]) -> Palette {
+        Palette {
+            colors: words.iter().map(|&word| RGB4::from(word)).collect(),
+        }
+    }
+
+    /// Rotate the entries `low..=high` by one step: forward moves each color
+    /// up one index with the last wrapping to `low`, `reverse` moves them
+    /// down. Parts of the range past the end of the palette are ignored.
+    pub fn cycle_range(&mut self, low: usize, high: usize, reverse: bool) {
+        let high = high.min(self.colors.len().saturating_sub(1));
+        if low >= high {
+            return;
+        }
+        let range = &mut self.colors[low..=high];
+        if reverse {
+            range.rotate_left(1);
+        } else {
+            range.rotate_right(1);
+        }
+    }
+
     pub fn get_color(&self, index: usize) -> Option<&RGB4> {
         self.colors.get(index)
     }
 
     /**
      * Create a lookup table converting palette indices to RGBA32 colors, but only
-     * to the specified depth.
+     * to the specified depth. Depths past 5 are for EHB (6) and AGA (up to 8)
+     * images whose colormaps have that many entries.
      */
     pub fn to_rgba32_table(&self, depth: usize) -> Result<Vec<u32>, String> {
-        if depth < 1 || depth > 5 {
-            return Err("Palette depth must be 1 to 5 inclusive".to_string());
+        if !(1..=8).contains(&depth) {
+            return Err("Palette depth must be 1 to 8 inclusive".to_string());
         }
 
         let mut table: Vec<u32> = Vec::with_capacity(1 << depth);
@@ -120,6 +251,186 @@ impl Palette {
         }
         Ok(table)
     }
+
+    /// Find the palette entry closest to the given 8-bit RGB color, using
+    /// squared Euclidean distance. Ties resolve to the lowest index.
+    pub fn nearest_index(&self, r: u8, g: u8, b: u8) -> usize {
+        let mut best_index = 0;
+        let mut best_dist = u32::MAX;
+        for (index, c) in self.colors.iter().enumerate() {
+            let dist = distance_sq(c, (r, g, b));
+            if dist < best_dist {
+                best_dist = dist;
+                best_index = index;
+            }
+        }
+        best_index
+    }
+
+    /// Index of the entry with the highest contrast ratio against `bg`, for
+    /// picking legible text colors. Ties resolve to the lowest index, and an
+    /// empty palette yields 0.
+    pub fn best_contrast_against(&self, bg: &RGB4) -> usize {
+        let mut best_index = 0;
+        let mut best_ratio = 0.0;
+        for (index, c) in self.colors.iter().enumerate() {
+            let ratio = c.contrast_ratio(bg);
+            if ratio > best_ratio {
+                best_ratio = ratio;
+                best_index = index;
+            }
+        }
+        best_index
+    }
+
+    /// Index pairs `(i, j)`, `i < j`, whose colors are identical.
+    pub fn duplicates(&self) -> Vec<(usize, usize)> {
+        self.pairs_where(|a, b| a.color & 0xFFF == b.color & 0xFFF)
+    }
+
+    /// Index pairs `(i, j)`, `i < j`, whose colors are within `threshold`
+    /// (Euclidean distance in 8-bit RGB) of each other. Identical colors are
+    /// included; a threshold of 17 catches colors one 4-bit step apart.
+    pub fn near_duplicates(&self, threshold: u32) -> Vec<(usize, usize)> {
+        let limit = threshold.saturating_mul(threshold);
+        self.pairs_where(|a, b| distance_sq(a, (b.r(), b.g(), b.b())) <= limit)
+    }
+
+    fn pairs_where<F: Fn(&RGB4, &RGB4) -> bool>(&self, matches: F) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (i, a) in self.colors.iter().enumerate() {
+            for (j, b) in self.colors.iter().enumerate().skip(i + 1) {
+                if matches(a, b) {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    /**
+     * Convert an RGBA32 pixel buffer (R, G, B, A byte order, tightly packed)
+     * into palette indices, one per pixel. This is the inverse of
+     * `BitMap::generate_rgb32`.
+     *
+     * If `transparent_index` is provided, pixels with alpha below
+     * `QUANTIZE_ALPHA_THRESHOLD` map to that index instead of their nearest color.
+     */
+    pub fn quantize_rgba(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        transparent_index: Option<usize>,
+    ) -> Vec<usize> {
+        pixels
+            .chunks_exact(4)
+            .take(width * height)
+            .map(|px| match transparent_index {
+                Some(index) if px[3] < QUANTIZE_ALPHA_THRESHOLD => index,
+                _ => self.nearest_index(px[0], px[1], px[2]),
+            })
+            .collect()
+    }
+
+    /**
+     * Like `quantize_rgba`, but dithers to reduce banding on gradients.
+     * Alpha is ignored; every pixel maps to its (dithered) nearest color.
+     */
+    pub fn quantize_rgba_dithered(
+        &self,
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+        mode: DitherMode,
+    ) -> Vec<usize> {
+        let clamp = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+        let mut indices: Vec<usize> = Vec::with_capacity(width * height);
+        if self.colors.is_empty() {
+            // nothing to pick from; every pixel maps to 0, as in nearest_index
+            indices.resize(pixels.chunks_exact(4).take(width * height).count(), 0);
+            return indices;
+        }
+
+        match mode {
+            DitherMode::Ordered => {
+                let spread = self.ordered_dither_spread();
+                for (pos, px) in pixels.chunks_exact(4).take(width * height).enumerate() {
+                    let (xx, yy) = (pos % width, pos / width);
+                    let threshold = (BAYER_4X4[yy & 3][xx & 3] as f32 + 0.5) / 16.0 - 0.5;
+                    let bias = threshold * spread;
+                    indices.push(self.nearest_index(
+                        clamp(px[0] as f32 + bias),
+                        clamp(px[1] as f32 + bias),
+                        clamp(px[2] as f32 + bias),
+                    ));
+                }
+            }
+            DitherMode::FloydSteinberg => {
+                // working copy of RGB values that accumulates diffused error
+                let mut work: Vec<[f32; 3]> = pixels
+                    .chunks_exact(4)
+                    .take(width * height)
+                    .map(|px| [px[0] as f32, px[1] as f32, px[2] as f32])
+                    .collect();
+
+                for pos in 0..work.len() {
+                    let (xx, yy) = (pos % width, pos / width);
+                    let old = work[pos];
+                    let index = self.nearest_index(clamp(old[0]), clamp(old[1]), clamp(old[2]));
+                    indices.push(index);
+
+                    let chosen = &self.colors[index];
+                    let error = [
+                        old[0] - chosen.r() as f32,
+                        old[1] - chosen.g() as f32,
+                        old[2] - chosen.b() as f32,
+                    ];
+                    // neighbours past the end of short input are simply skipped
+                    let mut diffuse = |dx: isize, dy: usize, weight: f32| {
+                        let nx = xx as isize + dx;
+                        let ny = yy + dy;
+                        if nx >= 0 && (nx as usize) < width && ny < height {
+                            if let Some(target) = work.get_mut(ny * width + nx as usize) {
+                                for (channel, err) in target.iter_mut().zip(error.iter()) {
+                                    *channel += err * weight;
+                                }
+                            }
+                        }
+                    };
+                    diffuse(1, 0, 7.0 / 16.0);
+                    diffuse(-1, 1, 3.0 / 16.0);
+                    diffuse(0, 1, 5.0 / 16.0);
+                    diffuse(1, 1, 1.0 / 16.0);
+                }
+            }
+        }
+
+        indices
+    }
+
+    // Ordered dither spread: the mean distance from each color to its nearest
+    // distinct neighbour, per channel in 8-bit units, so the bayer bias can
+    // carry a pixel across to the next color however coarse the palette is.
+    fn ordered_dither_spread(&self) -> f32 {
+        let nearest: Vec<f32> = self
+            .colors
+            .iter()
+            .filter_map(|a| {
+                self.colors
+                    .iter()
+                    .filter(|b| b.color != a.color)
+                    .map(|b| distance_sq(b, (a.r(), a.g(), a.b())))
+                    .min()
+            })
+            .map(|dist| (dist as f32 / 3.0).sqrt())
+            .collect();
+        if nearest.is_empty() {
+            0.0
+        } else {
+            nearest.iter().sum::<f32>() / nearest.len() as f32
+        }
+    }
 }
 
 fn deserialize_rgb4_vec<'de, D>(deserializer: D) -> Result<Vec<RGB4>, D::Error>
@@ -134,6 +445,26 @@ where
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cycle_range() {
+        let mut palette = Palette {
+            colors: (0..6u16).map(RGB4::from).collect(),
+        };
+        palette.cycle_range(1, 3, false);
+        let colors: Vec<u16> = palette.colors.iter().map(|c| c.color).collect();
+        assert_eq!(colors, vec![0, 3, 1, 2, 4, 5]);
+
+        palette.cycle_range(1, 3, true);
+        let colors: Vec<u16> = palette.colors.iter().map(|c| c.color).collect();
+        assert_eq!(colors, vec![0, 1, 2, 3, 4, 5]);
+
+        // clipped to the palette, and a one-entry range is a no-op
+        palette.cycle_range(4, 40, false);
+        palette.cycle_range(2, 2, false);
+        let colors: Vec<u16> = palette.colors.iter().map(|c| c.color).collect();
+        assert_eq!(colors, vec![0, 1, 2, 3, 5, 4]);
+    }
+
     #[test]
     fn test_rgb4_conversion() {
         let c1 = RGB4::from((0xAB, 0xCD, 0xEF));
@@ -149,6 +480,174 @@ mod tests {
         assert_eq!(color.b, 0xEE);
     }
 
+    // Horizontal black-to-white ramp, identical on every row.
+    fn gradient_rgba(width: usize, height: usize) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for _ in 0..height {
+            for xx in 0..width {
+                let v = (xx * 255 / (width - 1)) as u8;
+                pixels.extend_from_slice(&[v, v, v, 0xFF]);
+            }
+        }
+        pixels
+    }
+
+    fn black_white_palette() -> Palette {
+        Palette {
+            colors: vec![RGB4::from(0x000), RGB4::from(0xFFF)],
+        }
+    }
+
+    // Number of index changes along a row; a hard boundary has exactly one.
+    fn transitions(row: &[usize]) -> usize {
+        row.windows(2).filter(|pair| pair[0] != pair[1]).count()
+    }
+
+    #[test]
+    fn test_quantize_floyd_steinberg_mixes_gradient() {
+        let palette = black_white_palette();
+        let (width, height) = (32, 4);
+        let pixels = gradient_rgba(width, height);
+
+        let plain = palette.quantize_rgba(&pixels, width, height, None);
+        assert_eq!(transitions(&plain[0..width]), 1);
+
+        let dithered =
+            palette.quantize_rgba_dithered(&pixels, width, height, DitherMode::FloydSteinberg);
+        assert_eq!(dithered.len(), width * height);
+        let middle = &dithered[width + width / 4..width + 3 * width / 4];
+        assert!(middle.contains(&0) && middle.contains(&1));
+        assert!(transitions(&dithered[width..2 * width]) > 1);
+        // the ends of the ramp stay solid
+        assert_eq!(dithered[0], 0);
+        assert_eq!(dithered[width - 1], 1);
+    }
+
+    #[test]
+    fn test_quantize_ordered_mixes_gradient() {
+        let palette = black_white_palette();
+        let (width, height) = (32, 4);
+        let pixels = gradient_rgba(width, height);
+        let dithered = palette.quantize_rgba_dithered(&pixels, width, height, DitherMode::Ordered);
+        assert_eq!(dithered.len(), width * height);
+        // the bias spans the gap between black and white, so the middle of
+        // the ramp mixes both colors instead of switching at one column
+        for row in dithered.chunks(width) {
+            let middle = &row[width / 4..3 * width / 4];
+            assert!(middle.contains(&0) && middle.contains(&1));
+            assert!(transitions(row) > 1);
+            // the ends of the ramp stay solid
+            assert_eq!(row[0], 0);
+            assert_eq!(row[width - 1], 1);
+        }
+    }
+
+    #[test]
+    fn test_quantize_dithered_short_input_and_empty_palette() {
+        let palette = black_white_palette();
+        // 4x4 image with only 5 pixels of data
+        let pixels = gradient_rgba(5, 1);
+        for mode in [DitherMode::Ordered, DitherMode::FloydSteinberg] {
+            assert_eq!(palette.quantize_rgba_dithered(&pixels, 4, 4, mode).len(), 5);
+            let empty = Palette::default();
+            assert_eq!(
+                empty.quantize_rgba_dithered(&pixels, 4, 4, mode),
+                vec![0; 5]
+            );
+        }
+    }
+
+    #[test]
+    fn test_rgb4_blend_endpoints() {
+        let a = RGB4::from(0x1A3);
+        let b = RGB4::from(0xE4C);
+        assert_eq!(a.blend(&b, 0.0).color, 0x1A3);
+        assert_eq!(a.blend(&b, 1.0).color, 0xE4C);
+        // out of range weights clamp to the endpoints
+        assert_eq!(a.blend(&b, -2.0).color, 0x1A3);
+        assert_eq!(a.blend(&b, 3.0).color, 0xE4C);
+    }
+
+    #[test]
+    fn test_rgb4_blend_midpoint_rounding() {
+        let black = RGB4::from(0x000);
+        let white = RGB4::from(0xFFF);
+        // 7.5 rounds up to 8 in every component
+        assert_eq!(black.blend(&white, 0.5).color, 0x888);
+        // 0x1 -> 0xE: 7.5 -> 8, 0xA -> 0x4: 7, 0x3 -> 0xC: 7.5 -> 8
+        let a = RGB4::from(0x1A3);
+        let b = RGB4::from(0xE4C);
+        assert_eq!(a.blend(&b, 0.5).color, 0x878);
+    }
+
+    #[test]
+    fn test_contrast_ratio_and_best_pick() {
+        let black = RGB4::from(0x000);
+        let white = RGB4::from(0xFFF);
+        assert!((black.contrast_ratio(&white) - 21.0).abs() < 0.01);
+        assert!((white.contrast_ratio(&black) - 21.0).abs() < 0.01);
+        assert!((white.contrast_ratio(&white) - 1.0).abs() < 0.001);
+
+        let palette = Palette::from_copper_words(&[0xEEE, 0x888, 0x222, 0xCB9]);
+        assert_eq!(palette.best_contrast_against(&RGB4::from(0xFFD)), 2);
+        assert_eq!(palette.best_contrast_against(&black), 0);
+    }
+
+    #[test]
+    fn test_palette_ramp_black_to_white() {
+        let ramp = Palette::ramp(RGB4::from(0x000), RGB4::from(0xFFF), 5);
+        let colors: Vec<u16> = ramp.colors.iter().map(|c| c.color).collect();
+        // 15 * 0.25 = 3.75 -> 4, 15 * 0.75 = 11.25 -> 11
+        assert_eq!(colors, vec![0x000, 0x444, 0x888, 0xBBB, 0xFFF]);
+
+        assert!(Palette::ramp(RGB4::from(0x000), RGB4::from(0xFFF), 0)
+            .colors
+            .is_empty());
+        let single = Palette::ramp(RGB4::from(0x123), RGB4::from(0xFFF), 1);
+        assert_eq!(single.colors.len(), 1);
+        assert_eq!(single.colors[0].color, 0x123);
+    }
+
+    #[test]
+    fn test_copper_words_round_trip() {
+        let palette = Palette {
+            colors: (0..32u16).map(|i| RGB4::from(i * 0x083 + 0x00F)).collect(),
+        };
+        let words = palette.to_copper_words();
+        assert_eq!(words.len(), 32);
+        for (word, color) in words.iter().zip(&palette.colors) {
+            assert_eq!(*word, color.color);
+        }
+
+        let rebuilt = Palette::from_copper_words(&words);
+        let rebuilt_colors: Vec<u16> = rebuilt.colors.iter().map(|c| c.color).collect();
+        assert_eq!(rebuilt_colors, words);
+
+        assert_eq!(Palette::from_copper_words(&[0xF123]).colors[0].color, 0x123);
+    }
+
+    #[test]
+    fn test_palette_duplicates() {
+        let palette = Palette {
+            colors: vec![
+                RGB4::from(0x000),
+                RGB4::from(0xA50),
+                RGB4::from(0xFFF),
+                RGB4::from(0xA50),
+                RGB4::from(0xA60),
+            ],
+        };
+        assert_eq!(palette.duplicates(), vec![(1, 3)]);
+        assert_eq!(palette.near_duplicates(17), vec![(1, 3), (1, 4), (3, 4)]);
+        assert_eq!(palette.near_duplicates(0), vec![(1, 3)]);
+
+        let distinct = Palette {
+            colors: vec![RGB4::from(0x000), RGB4::from(0xF00), RGB4::from(0x0F0)],
+        };
+        assert!(distinct.duplicates().is_empty());
+        assert!(distinct.near_duplicates(17).is_empty());
+    }
+
     #[test]
     fn test_palette_deserialization() {
         let toml_data = r#"
@@ -168,6 +667,34 @@ mod tests {
         assert_eq!(palette.colors[0].b(), 0xEE);
     }
 
+    #[test]
+    fn test_quantize_rgba() {
+        let palette = Palette {
+            colors: vec![
+                RGB4::from(0x000),
+                RGB4::from(0xFFF),
+                RGB4::from(0xE00),
+                RGB4::from(0x390),
+            ],
+        };
+        let pixels: Vec<u8> = [
+            [0x00, 0x00, 0x00, 0xFF], // black
+            [0xF0, 0xF0, 0xF0, 0xFF], // near white
+            [0xE0, 0x10, 0x00, 0xFF], // near red
+            [0x30, 0xA0, 0x10, 0xFF], // near green
+            [0xFF, 0xFF, 0xFF, 0x00], // transparent white
+            [0xEE, 0x00, 0x00, 0x7F], // mostly transparent red
+        ]
+        .concat();
+
+        let indices = palette.quantize_rgba(&pixels, 3, 2, Some(0));
+        assert_eq!(indices, vec![0, 1, 2, 3, 0, 0]);
+
+        // without a transparent index, alpha is ignored
+        let indices = palette.quantize_rgba(&pixels, 3, 2, None);
+        assert_eq!(indices, vec![0, 1, 2, 3, 1, 2]);
+    }
+
     #[test]
     fn test_palette_to_rgba32_table() {
         let toml_data = r#"
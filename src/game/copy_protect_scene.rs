@@ -7,6 +7,7 @@ use sdl3::video::Window;
 
 use std::any::Any;
 
+use crate::game::colors::Palette;
 use crate::game::game_library::GameLibrary;
 use crate::game::palette_fader::{FadeController, FadeResult};
 use crate::game::scene::{Scene, SceneResources, SceneResult};
@@ -234,9 +235,13 @@ impl Scene for CopyProtectScene {
                         play_canvas.set_draw_color(BG_COLOR);
                         play_canvas.clear();
 
+                        let fallback_palette = Palette::default();
+                        let copyjunk_palette = game_lib
+                            .find_palette("copyjunk")
+                            .unwrap_or(&fallback_palette);
                         if let Some(placard) = game_lib.find_placard("copy_junk") {
                             resources.topaz_font.set_color_mod(255, 255, 255);
-                            placard.draw(resources.topaz_font, play_canvas);
+                            placard.draw(resources.topaz_font, play_canvas, copyjunk_palette);
                         }
                     });
                     *drawn = true;
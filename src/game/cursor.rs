@@ -29,3 +29,43 @@ pub struct CursorAsset {
     pub hotspot: Hotspot,
     pub bitmap: BitMap,
 }
+
+impl CursorAsset {
+    /// Copy of this cursor enlarged by an integer `factor` to match an
+    /// upscaled display, with the hotspot scaled to stay on the same pixel.
+    pub fn scaled(&self, factor: usize) -> Result<CursorAsset, String> {
+        Ok(CursorAsset {
+            hotspot: Hotspot {
+                x: self.hotspot.x * factor,
+                y: self.hotspot.y * factor,
+            },
+            bitmap: self.bitmap.scale_nearest(factor)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaled_doubles_bitmap_and_hotspot() {
+        let mut bitmap = BitMap::build(5, 3, 2).unwrap();
+        bitmap.set_pixel(1, 2, 3);
+        let cursor = CursorAsset {
+            hotspot: Hotspot { x: 1, y: 2 },
+            bitmap,
+        };
+
+        let scaled = cursor.scaled(2).unwrap();
+        assert_eq!(scaled.bitmap.get_size(), (10, 6));
+        assert_eq!((scaled.hotspot.x, scaled.hotspot.y), (2, 4));
+        // the hotspot pixel is still the one that was set
+        assert_eq!(scaled.bitmap.get_pixel(2, 4), 3);
+        assert_eq!(scaled.bitmap.get_pixel(3, 5), 3);
+        assert_eq!(scaled.bitmap.get_pixel(4, 4), 0);
+        assert!(scaled.bitmap.validate().is_ok());
+
+        assert!(cursor.scaled(0).is_err());
+    }
+}
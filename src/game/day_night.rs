@@ -0,0 +1,174 @@
+use crate::game::colors::Palette;
+use crate::game::game_clock::GameClock;
+use crate::game::image_texture::ImageTexture;
+use crate::game::render_task::RenderTask;
+
+use sdl3::rect::Rect;
+use sdl3::render::Canvas;
+use sdl3::video::Window;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/*
+ * Day/night dimming render task. Each update reads the clock's light level
+ * (0 at midnight, 300 at midday), scales the base palette by the matching
+ * brightness and writes it to the shared scene palette. The textures are
+ * rebuilt and re-uploaded only when the dimmed palette actually changes,
+ * which is a few times per game hour at most. Runs until cancelled.
+ */
+
+// get_light_level() at midday
+const FULL_LIGHT_LEVEL: f32 = 300.0;
+// darkest brightness reached at night, so the scene never goes fully black
+const NIGHT_BRIGHTNESS: f32 = 0.25;
+
+pub struct DayNightTask<'tex> {
+    clock: Rc<RefCell<GameClock>>,
+    base_palette: Palette,
+    palette: Rc<RefCell<Palette>>,
+    textures: Vec<ImageTexture<'tex>>,
+    key_color: Option<usize>,
+
+    // brightness factor applied by the last step
+    brightness: f32,
+    // number of times the textures have been rebuilt
+    uploads: usize,
+}
+
+impl<'tex> DayNightTask<'tex> {
+    /// `palette` is the shared scene palette that gets overwritten with the
+    /// dimmed colors; its current contents are kept as the full-brightness
+    /// base.
+    pub fn new(
+        clock: Rc<RefCell<GameClock>>,
+        palette: Rc<RefCell<Palette>>,
+        textures: Vec<ImageTexture<'tex>>,
+        key_color: Option<usize>,
+    ) -> DayNightTask<'tex> {
+        let base_palette = palette.borrow().clone();
+        DayNightTask {
+            clock,
+            base_palette,
+            palette,
+            textures,
+            key_color,
+            brightness: 1.0,
+            uploads: 0,
+        }
+    }
+
+    /// Brightness factor for a light level: linear from `NIGHT_BRIGHTNESS`
+    /// up to 1.0 at full daylight.
+    pub fn brightness_for(light_level: u16) -> f32 {
+        (light_level as f32 / FULL_LIGHT_LEVEL).clamp(NIGHT_BRIGHTNESS, 1.0)
+    }
+
+    pub fn brightness(&self) -> f32 {
+        self.brightness
+    }
+
+    pub fn uploads(&self) -> usize {
+        self.uploads
+    }
+
+    pub fn textures(&self) -> &[ImageTexture<'tex>] {
+        &self.textures
+    }
+
+    /// Apply the brightness for the clock's current light level, re-uploading
+    /// the textures if the dimmed palette changed.
+    pub fn step(&mut self) {
+        self.brightness = DayNightTask::brightness_for(self.clock.borrow().get_light_level());
+        let dimmed = self.base_palette.scaled_brightness(self.brightness);
+        if dimmed.to_copper_words() == self.palette.borrow().to_copper_words() {
+            return;
+        }
+
+        *self.palette.borrow_mut() = dimmed;
+        let palette = self.palette.borrow();
+        for texture in self.textures.iter_mut() {
+            texture.update(&palette, self.key_color);
+        }
+        self.uploads += 1;
+    }
+}
+
+impl RenderTask for DayNightTask<'_> {
+    fn update(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        _delta_ticks: i32,
+        _area: Option<Rect>,
+    ) -> bool {
+        self.step();
+        for texture in self.textures.iter() {
+            let bounds = texture.get_bounds();
+            texture.draw(canvas, bounds.x(), bounds.y());
+        }
+        true
+    }
+
+    fn cancel(&mut self) {
+        // leave the scene palette at full brightness
+        *self.palette.borrow_mut() = self.base_palette.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::rc::Weak;
+
+    #[test]
+    fn test_brightness_follows_light_level() {
+        let clock = Rc::new(RefCell::new(GameClock::new()));
+        let palette = Rc::new(RefCell::new(Palette::from_copper_words(&[
+            0x000, 0xFFF, 0x8C4,
+        ])));
+        let texture = ImageTexture::placeholder(&Rect::new(0, 0, 8, 8), Weak::new());
+        let mut task = DayNightTask::new(clock.clone(), palette.clone(), vec![texture], None);
+
+        let mut factors = Vec::new();
+        for daynight in [0, 3_000, 6_000, 9_000, 12_000] {
+            clock.borrow_mut().set_daynight(daynight);
+            task.step();
+            factors.push(task.brightness());
+        }
+        // midnight sits at the night floor, then light rises to midday
+        assert_eq!(factors[0], NIGHT_BRIGHTNESS);
+        assert!(
+            factors.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{:?}",
+            factors
+        );
+        assert_eq!(factors[4], 1.0);
+        assert_eq!(
+            palette.borrow().to_copper_words(),
+            vec![0x000, 0xFFF, 0x8C4]
+        );
+        let uploads = task.uploads();
+        assert!(uploads >= 3);
+
+        // the same light level again doesn't re-upload
+        task.step();
+        assert_eq!(task.uploads(), uploads);
+
+        clock.borrow_mut().set_daynight(23_000);
+        task.step();
+        assert_eq!(task.brightness(), NIGHT_BRIGHTNESS);
+        // 15 * 0.25 = 3.75 -> 4, 8 -> 2, 12 -> 3, 4 -> 1
+        assert_eq!(
+            palette.borrow().to_copper_words(),
+            vec![0x000, 0x444, 0x231]
+        );
+        assert_eq!(task.uploads(), uploads + 1);
+
+        task.cancel();
+        assert_eq!(
+            palette.borrow().to_copper_words(),
+            vec![0x000, 0xFFF, 0x8C4]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::game::bitmap::BitMap;
 use crate::game::byteops::*;
 use crate::game::hunk::*;
 
@@ -32,6 +33,11 @@ pub const FPF_WIDEDOT: u8 = 0x10; // designed for 320x400 (lores interlaced) mod
 pub const FPF_PROPORTIONAL: u8 = 0x20;
 pub const FPF_DESIGNED: u8 = 0x40;
 
+// Font cache file: magic + version, then the DiskFont fields big-endian.
+// Bump the version whenever the layout changes so stale caches get rejected.
+const FONT_CACHE_MAGIC: &[u8; 4] = b"FTFC";
+const FONT_CACHE_VERSION: u16 = 1;
+
 // asset type used by GameLibrary
 // This only considers font size, not styles, because I don't need to do otherwise
 #[derive(Debug, Deserialize)]
@@ -169,7 +175,7 @@ pub fn load_font_file(path: &Path) -> Option<FontFile> {
     Some(fontfile)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct DiskFont {
     pub name: String,     // name of this font (might be empty)
     pub y_size: usize,    // # pixels high
@@ -298,53 +304,161 @@ impl DiskFont {
         Rect::new(0_i32, 0_i32, self.modulo as u32, self.y_size as u32)
     }
 
+    /// Pixels from the baseline up to the top of the glyph cell.
+    pub fn ascent(&self) -> usize {
+        self.baseline
+    }
+
+    /// Pixels from the baseline down to the bottom of the glyph cell.
+    pub fn descent(&self) -> usize {
+        self.y_size.saturating_sub(self.baseline)
+    }
+
+    /// Calculate the pixel width of a string rendered in this font.
+    /// Characters outside `lo_char..=hi_char` are skipped, as when rendering.
+    pub fn string_width(&self, s: &str) -> i32 {
+        self.measure(s, false)
+    }
+
+    /// Pixel width of `s` as the original's text-centering code summed it from
+    /// the width table: `char_space` per character for proportional fonts,
+    /// `x_size` per character for monospace, out of range characters skipped.
+    /// Unlike `string_width`, tabs count as spaces.
+    pub fn text_width(&self, s: &str) -> i32 {
+        self.measure(s, true)
+    }
+
+    // Sum of the advances of every in-range byte of `s`, counting tabs as
+    // spaces when `tab_as_space` is set.
+    fn measure(&self, s: &str, tab_as_space: bool) -> i32 {
+        let mut width: i32 = 0;
+        for cc in s.bytes() {
+            let cc = if tab_as_space && cc == b'\t' {
+                b' '
+            } else {
+                cc
+            };
+            if cc < self.lo_char || cc > self.hi_char {
+                continue;
+            }
+            width += if self.is_proportional() {
+                self.char_space[(cc - self.lo_char) as usize] as i32
+            } else {
+                self.x_size as i32
+            };
+        }
+        width
+    }
+
+    /// Lay out every glyph from `lo_char` to `hi_char` in a grid `columns`
+    /// cells wide, for checking a font by eye. Cells are `y_size` tall and as
+    /// wide as `x_size` or the widest glyph, whichever is larger. Glyph pixels
+    /// are index 1 in a depth-1 bitmap.
+    pub fn preview_bitmap(&self, columns: usize) -> BitMap {
+        let columns = columns.max(1);
+        let glyph_count = self.char_loc.len();
+        let cell_width = self
+            .char_loc
+            .iter()
+            .map(|&(_, width)| width)
+            .fold(self.x_size, usize::max)
+            .max(1);
+        let cell_height = self.y_size.max(1);
+        let rows = glyph_count.div_ceil(columns).max(1);
+
+        let mut preview = BitMap::build(columns * cell_width, rows * cell_height, 1).unwrap();
+        for (index, &(bit_offset, bit_width)) in self.char_loc.iter().enumerate() {
+            let cell_x = (index % columns) * cell_width;
+            let cell_y = (index / columns) * cell_height;
+            for row in 0..self.y_size {
+                let row_start = self.modulo * row + bit_offset;
+                for col in 0..bit_width {
+                    if self.char_data[row_start + col] != 0 {
+                        preview.set_pixel(cell_x + col, cell_y + row, 1);
+                    }
+                }
+            }
+        }
+        preview
+    }
+
     pub fn print(&self, s: &str) {
+        for row in self.text_rows(s) {
+            println!("{}", row);
+        }
+    }
+
+    // Render a string as one text row per font scanline, each glyph placed
+    // side by side using its advance width. Characters outside the font range
+    // are skipped.
+    fn text_rows(&self, s: &str) -> Vec<String> {
         // we need to ensure the string is ascii, and get a byte slice from it
         let cstr = s.as_bytes();
 
-        for yy in 0..self.y_size {
-            for pc in 0..cstr.len() {
-                if cstr[pc] >= self.lo_char && cstr[pc] <= self.hi_char {
-                    self.print_char_line((cstr[pc] - self.lo_char) as usize, yy, false);
+        (0..self.y_size)
+            .map(|yy| {
+                let mut row = String::new();
+                for cc in cstr {
+                    if *cc >= self.lo_char && *cc <= self.hi_char {
+                        let char_index = (*cc - self.lo_char) as usize;
+                        row.push_str(&self.char_line(char_index, yy, false));
+                    }
                 }
-            }
-            println!("");
+                row
+            })
+            .collect()
+    }
+
+    // Horizontal advance of a glyph, in pixels. Monospace fonts have no
+    // spacing table and always advance by x_size.
+    fn char_advance(&self, char_index: usize) -> usize {
+        if self.is_proportional() {
+            self.char_space[char_index].max(0) as usize // negative spacing not meaningful for terminal debug output
+        } else {
+            self.x_size
         }
     }
 
     // print a single line of the given character
-    // if the char is invalid (not ascii, not in the font range) then do nothing
     fn print_char_line(&self, char_index: usize, line: usize, mark: bool) {
+        print!("{}", self.char_line(char_index, line, mark));
+    }
+
+    // format a single line of the given character, padded out to its advance width
+    fn char_line(&self, char_index: usize, line: usize, mark: bool) -> String {
         // get char location
         let char_loc = self.char_loc[char_index];
         let offset = (self.modulo * line) + char_loc.0;
-        let total_width = self.char_space[char_index].max(0) as usize; // negative spacing not meaningful for terminal debug output
+        let total_width = self.char_advance(char_index);
+        let pad = total_width.saturating_sub(char_loc.1);
         let is_baseline = line == self.baseline;
+        let mut out = String::new();
 
         if mark {
-            print!("|");
+            out.push('|');
         }
 
         for xx in 0..char_loc.1 {
             let cc = self.char_data[offset + xx];
             if cc > 0 {
-                print!("#");
+                out.push('#');
             } else {
-                print!("{}", if is_baseline && mark { "-" } else { " " });
+                out.push(if is_baseline && mark { '-' } else { ' ' });
             }
         }
 
         // fill out to the total width
         if mark {
             if is_baseline {
-                print!("{:->1$}", "|", total_width - char_loc.1 + 1);
+                out.push_str(&format!("{:->1$}", "|", pad + 1));
             } else {
-                print!("{: >1$}", "|", total_width - char_loc.1 + 1);
+                out.push_str(&format!("{: >1$}", "|", pad + 1));
             }
         } else {
             // still need to fill out to the total width
-            print!("{: >1$}", "", total_width - char_loc.1);
+            out.push_str(&" ".repeat(pad));
         }
+        out
     }
 
     // Print every character glyph in the font to the terminal
@@ -358,7 +472,7 @@ impl DiskFont {
     fn print_char(&self, c: u8, mark: bool) {
         if c >= self.lo_char && c <= self.hi_char {
             let char_index = (c - self.lo_char) as usize;
-            let total_width = self.char_space[char_index].max(0) as usize;
+            let total_width = self.char_advance(char_index);
 
             // make sure it's a printable char first
             let char_str = if c.is_ascii_graphic() {
@@ -381,6 +495,112 @@ impl DiskFont {
             }
         }
     }
+
+    /**
+     * Serialize this font to the cache format, so later launches can skip
+     * parsing the hunk file. See `load_cache`.
+     */
+    pub fn to_cache_bytes(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::new();
+        data.extend_from_slice(FONT_CACHE_MAGIC);
+        data.extend_from_slice(&FONT_CACHE_VERSION.to_be_bytes());
+
+        data.extend_from_slice(&(self.name.len() as u32).to_be_bytes());
+        data.extend_from_slice(self.name.as_bytes());
+        for value in [self.y_size, self.x_size] {
+            data.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        data.extend_from_slice(&[self.style, self.flags]);
+        for value in [self.baseline, self.boldsmear] {
+            data.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        data.extend_from_slice(&[self.lo_char, self.hi_char]);
+
+        data.extend_from_slice(&(self.modulo as u32).to_be_bytes());
+        data.extend_from_slice(&(self.char_data.len() as u32).to_be_bytes());
+        data.extend_from_slice(&self.char_data);
+
+        data.extend_from_slice(&(self.char_loc.len() as u32).to_be_bytes());
+        for (offset, len) in &self.char_loc {
+            data.extend_from_slice(&(*offset as u32).to_be_bytes());
+            data.extend_from_slice(&(*len as u32).to_be_bytes());
+        }
+        for table in [&self.char_space, &self.char_kern] {
+            data.extend_from_slice(&(table.len() as u32).to_be_bytes());
+            for value in table {
+                data.extend_from_slice(&(*value as i32).to_be_bytes());
+            }
+        }
+        data
+    }
+
+    /**
+     * Rebuild a font from `to_cache_bytes` output. Fails on a magic or version
+     * mismatch so the caller can fall back to parsing the original font.
+     */
+    pub fn from_cache_bytes(data: &[u8]) -> Result<DiskFont, String> {
+        let mut offset = 0;
+        if try_read_bytes(data, &mut offset, 4)? != FONT_CACHE_MAGIC {
+            return Err("Not a font cache file".to_string());
+        }
+        let version = try_read_u16(data, &mut offset)?;
+        if version != FONT_CACHE_VERSION {
+            return Err(format!(
+                "Font cache version {} does not match expected {}",
+                version, FONT_CACHE_VERSION
+            ));
+        }
+
+        let mut font = DiskFont::new();
+        let name_len = try_read_u32(data, &mut offset)? as usize;
+        font.name =
+            String::from_utf8_lossy(try_read_bytes(data, &mut offset, name_len)?).to_string();
+        font.y_size = try_read_u32(data, &mut offset)? as usize;
+        font.x_size = try_read_u32(data, &mut offset)? as usize;
+        font.style = try_read_u8(data, &mut offset)?;
+        font.flags = try_read_u8(data, &mut offset)?;
+        font.baseline = try_read_u32(data, &mut offset)? as usize;
+        font.boldsmear = try_read_u32(data, &mut offset)? as usize;
+        font.lo_char = try_read_u8(data, &mut offset)?;
+        font.hi_char = try_read_u8(data, &mut offset)?;
+
+        font.modulo = try_read_u32(data, &mut offset)? as usize;
+        let data_len = try_read_u32(data, &mut offset)? as usize;
+        font.char_data = try_read_bytes(data, &mut offset, data_len)?.to_vec();
+
+        let loc_count = try_read_u32(data, &mut offset)? as usize;
+        for _ in 0..loc_count {
+            let char_off = try_read_u32(data, &mut offset)? as usize;
+            let char_len = try_read_u32(data, &mut offset)? as usize;
+            font.char_loc.push((char_off, char_len));
+        }
+        for table in [&mut font.char_space, &mut font.char_kern] {
+            let count = try_read_u32(data, &mut offset)? as usize;
+            for _ in 0..count {
+                table.push(try_read_u32(data, &mut offset)? as i32 as isize);
+            }
+        }
+
+        if offset != data.len() {
+            return Err(format!(
+                "Font cache has {} bytes of trailing data",
+                data.len() - offset
+            ));
+        }
+        Ok(font)
+    }
+
+    pub fn save_cache(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_cache_bytes())
+            .map_err(|e| format!("Failed to write font cache {:?}: {}", path, e))
+    }
+
+    pub fn load_cache(path: &Path) -> Result<DiskFont, String> {
+        let data =
+            fs::read(path).map_err(|e| format!("Failed to read font cache {:?}: {}", path, e))?;
+        DiskFont::from_cache_bytes(&data)
+            .map_err(|e| format!("Invalid font cache {:?}: {}", path, e))
+    }
 }
 
 pub fn load_font(fontfile: &Path, name: &str) -> Result<DiskFont, String> {
@@ -466,6 +686,12 @@ pub fn load_font(fontfile: &Path, name: &str) -> Result<DiskFont, String> {
     offset += 2; // tf_Accessors (N/A)
     disk_font.lo_char = read_u8(hunk_data, &mut offset);
     disk_font.hi_char = read_u8(hunk_data, &mut offset);
+    if disk_font.hi_char < disk_font.lo_char {
+        return Err(format!(
+            "Font file {:?} has hi_char {} below lo_char {}",
+            fontfile, disk_font.hi_char, disk_font.lo_char
+        ));
+    }
 
     let font_data_offset = read_i32(hunk_data, &mut offset) as usize;
     disk_font.modulo = read_i16(hunk_data, &mut offset) as usize;
@@ -477,8 +703,8 @@ pub fn load_font(fontfile: &Path, name: &str) -> Result<DiskFont, String> {
     // println!("font data: {font_data_offset}, loc: {font_loc_offset}, space: {font_space_offset}, kern: {font_kern_offset}");
     // println!("end of DiskFont data. Offset = {offset}");
 
-    // char data length is modulo (bytes per row) * y size
-    let char_count = disk_font.hi_char as usize - disk_font.lo_char as usize;
+    // number of glyphs in lo_char..=hi_char, up to 256 for a full-range font
+    let char_count = disk_font.hi_char as usize - disk_font.lo_char as usize + 1;
 
     // copy the character data to disk_font
     // disk_font.char_data.extend_from_slice(&hunk_data[font_data_offset .. font_data_offset + data_len]);
@@ -521,7 +747,8 @@ pub fn load_font(fontfile: &Path, name: &str) -> Result<DiskFont, String> {
     // adjust modulo so it reflects the row size in char_data, which is now a byte array
     disk_font.modulo *= 8;
 
-    for index in 0..=char_count {
+    disk_font.char_loc.reserve(char_count);
+    for index in 0..char_count {
         // Load char locations and lengths
         offset = font_loc_offset + (index * 4);
         let char_off = read_u16(hunk_data, &mut offset) as usize;
@@ -549,3 +776,201 @@ pub fn load_font(fontfile: &Path, name: &str) -> Result<DiskFont, String> {
 
     Ok(disk_font)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three-glyph proportional font covering ' ', '!' and '"', with glyph
+    // bitmaps narrower than their advance widths.
+    fn proportional_font() -> DiskFont {
+        let mut font = DiskFont::new();
+        font.y_size = 2;
+        font.flags = FPF_PROPORTIONAL;
+        font.lo_char = 32;
+        font.hi_char = 34;
+        font.modulo = 8;
+        font.char_data = vec![
+            0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, //
+            0x00, 0xFF, 0x00, 0xFF, 0x00, 0x00, 0x00, 0xFF, //
+        ];
+        font.char_loc = vec![(0, 0), (1, 3), (4, 4)];
+        font.char_space = vec![3, 5, 6];
+        font.char_kern = vec![0, 0, 0];
+        font
+    }
+
+    // Build a single-hunk font file: a DiskFontHeader followed by one row of
+    // glyph data and a CharLoc table, monospace with no space/kern tables.
+    fn build_font_file(lo_char: u8, hi_char: u8) -> Vec<u8> {
+        let glyph_count = hi_char as usize - lo_char as usize + 1;
+        let modulo = (glyph_count * 8).div_ceil(16) * 2;
+        let char_data_offset = 110;
+        let char_loc_offset = char_data_offset + modulo;
+
+        let mut data: Vec<u8> = Vec::new();
+        data.extend_from_slice(&0x70FF4E75u32.to_be_bytes()); // MOVEQ #-1,D0; RTS
+        data.extend_from_slice(&[0; 8]); // ln_Succ, ln_Prev
+        data.extend_from_slice(&[12, 0]); // ln_Type = NT_FONT, ln_Pri
+        data.extend_from_slice(&[0; 4]); // ln_Name
+        data.extend_from_slice(&0x0F80u16.to_be_bytes()); // dfh_FileID
+        data.extend_from_slice(&[0; 6]); // dfh_Revision, dfh_Segment
+        data.extend_from_slice(&[0; 32]); // dfh_Name
+        data.extend_from_slice(&[0; 8]); // ln_Succ, ln_Prev
+        data.extend_from_slice(&[12, 0]); // ln_Type = NT_FONT, ln_Pri
+        data.extend_from_slice(&[0; 10]); // ln_Name, mn_ReplyPort, reserved
+        data.extend_from_slice(&1i16.to_be_bytes()); // tf_YSize
+        data.extend_from_slice(&[0, 0]); // tf_Style, tf_Flags
+        data.extend_from_slice(&8i16.to_be_bytes()); // tf_XSize
+        data.extend_from_slice(&[0; 6]); // tf_Baseline, tf_BoldSmear, tf_Accessors
+        data.extend_from_slice(&[lo_char, hi_char]);
+        data.extend_from_slice(&(char_data_offset as i32).to_be_bytes());
+        data.extend_from_slice(&(modulo as i16).to_be_bytes());
+        data.extend_from_slice(&(char_loc_offset as i32).to_be_bytes());
+        data.extend_from_slice(&[0; 8]); // tf_CharSpace, tf_CharKern
+        assert_eq!(data.len(), char_data_offset);
+
+        data.resize(char_loc_offset, 0xAA);
+        for index in 0..glyph_count {
+            data.extend_from_slice(&((index * 8) as u16).to_be_bytes());
+            data.extend_from_slice(&8u16.to_be_bytes());
+        }
+        // the hunk loader drops the last LONG of each hunk
+        data.resize(data.len().div_ceil(4) * 4 + 4, 0);
+
+        let mut file: Vec<u8> = Vec::new();
+        for long in [
+            0x03F3,
+            0,
+            1,
+            0,
+            0,
+            (data.len() / 4) as u32,
+            0x03EA,
+            (data.len() / 4) as u32,
+        ] {
+            file.extend_from_slice(&long.to_be_bytes());
+        }
+        file.extend_from_slice(&data);
+        file.extend_from_slice(&0x03F2u32.to_be_bytes()); // HUNK_END
+        file
+    }
+
+    #[test]
+    fn test_load_full_range_font() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("full.font");
+        std::fs::write(&path, build_font_file(0, 255)).unwrap();
+
+        let font = load_font(&path, "full").unwrap();
+        assert_eq!(font.lo_char, 0);
+        assert_eq!(font.hi_char, 255);
+        assert_eq!(font.char_loc.len(), 256);
+        assert_eq!(font.char_loc[255], (255 * 8, 8));
+    }
+
+    #[test]
+    fn test_load_font_rejects_inverted_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.font");
+        let mut file = build_font_file(32, 33);
+        // swap lo/hi in the TextFont header (hunk data starts after 32 bytes of headers)
+        file[32 + 90] = 33;
+        file[32 + 91] = 32;
+        std::fs::write(&path, file).unwrap();
+
+        assert!(load_font(&path, "bad").is_err());
+    }
+
+    #[test]
+    fn test_text_width_matches_width_table_sum() {
+        let mut font = proportional_font();
+        // '!' 5 + tab as ' ' 3 + '"' 6 + 'x' skipped + '!' 5
+        assert_eq!(font.text_width("!\t\"x!"), 5 + 3 + 6 + 5);
+        assert_eq!(font.string_width("!\t\"x!"), 5 + 6 + 5);
+
+        font.flags = 0;
+        font.x_size = 8;
+        assert_eq!(font.text_width("!\t\"x!"), 4 * 8);
+    }
+
+    #[test]
+    fn test_preview_bitmap_grid() {
+        let font = proportional_font();
+        let preview = font.preview_bitmap(2);
+        // three 4x2 cells (widest glyph is 4) in a 2 column grid
+        assert_eq!(preview.get_size(), (8, 4));
+        assert_eq!(preview.depth, 1);
+
+        // '!' sits in the second cell of the first row
+        let cell: Vec<usize> = (0..2)
+            .flat_map(|yy| (4..8).map(move |xx| (xx, yy)))
+            .map(|(xx, yy)| preview.get_pixel(xx, yy))
+            .collect();
+        assert_eq!(cell, vec![1, 1, 1, 0, 1, 0, 1, 0]);
+
+        // '"' wraps to the first cell of the second row
+        assert_eq!(preview.get_pixel(3, 3), 1);
+        assert_eq!(preview.get_pixel(0, 3), 0);
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let font_path = dir.path().join("rt.font");
+        std::fs::write(&font_path, build_font_file(32, 40)).unwrap();
+        let font = load_font(&font_path, "rt").unwrap();
+
+        let cache_path = dir.path().join("rt.cache");
+        font.save_cache(&cache_path).unwrap();
+        let cached = DiskFont::load_cache(&cache_path).unwrap();
+
+        assert_eq!(cached.name, font.name);
+        assert_eq!(cached.y_size, font.y_size);
+        assert_eq!(cached.x_size, font.x_size);
+        assert_eq!(cached.baseline, font.baseline);
+        assert_eq!((cached.lo_char, cached.hi_char), (32, 40));
+        assert_eq!(cached.char_line(3, 0, false), font.char_line(3, 0, false));
+        assert_eq!(cached, font);
+
+        // proportional tables survive too, including negative values
+        let mut font = proportional_font();
+        font.char_kern = vec![-1, 0, 2];
+        let cached = DiskFont::from_cache_bytes(&font.to_cache_bytes()).unwrap();
+        assert_eq!(cached, font);
+    }
+
+    #[test]
+    fn test_cache_rejects_stale_version() {
+        let mut data = proportional_font().to_cache_bytes();
+        data[5] = data[5].wrapping_add(1);
+        assert!(DiskFont::from_cache_bytes(&data).is_err());
+
+        let mut data = proportional_font().to_cache_bytes();
+        data[0] = b'X';
+        assert!(DiskFont::from_cache_bytes(&data).is_err());
+    }
+
+    #[test]
+    fn test_print_two_chars_row_aligned() {
+        let font = proportional_font();
+        let rows = font.text_rows("!\"");
+        assert_eq!(rows.len(), 2);
+        for row in &rows {
+            assert_eq!(row.len(), 5 + 6);
+        }
+        assert_eq!(rows[0], "###  ####  ");
+        assert_eq!(rows[1], "# #     #  ");
+    }
+
+    #[test]
+    fn test_print_monospace_uses_x_size() {
+        let mut font = proportional_font();
+        font.flags = 0;
+        font.x_size = 4;
+        font.char_space.clear();
+        font.char_kern.clear();
+        let rows = font.text_rows("!\"");
+        assert!(rows.iter().all(|row| row.len() == 8));
+    }
+}
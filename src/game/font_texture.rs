@@ -1,11 +1,80 @@
+use crate::game::colors::Palette;
 use crate::game::font::DiskFont;
 
+use sdl3::pixels::Color;
 use sdl3::rect::Rect;
 use sdl3::render::{Canvas, RenderTarget, Texture};
 
 use std::cell::RefCell;
 use std::rc::Weak;
 
+/// Horizontal layout direction for `render_string_dir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft, // x is the right edge; glyphs advance leftward
+}
+
+// (src, dst) rect of each glyph to copy from the font texture.
+type GlyphRects = Vec<(Rect, Rect)>;
+
+/// A span of text drawn in one color, from `parse_color_escapes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorRun {
+    pub text: String,
+    pub color: Option<usize>, // palette index, or None for the default color
+}
+
+/// Split `s` into colored runs at inline escapes: `{cN}` switches to palette
+/// index N and `{/}` goes back to the default color. Anything else in braces
+/// is kept as literal text. Empty runs are dropped.
+pub fn parse_color_escapes(s: &str) -> Vec<ColorRun> {
+    let mut runs: Vec<ColorRun> = Vec::new();
+    let mut current = ColorRun {
+        text: String::new(),
+        color: None,
+    };
+    let mut rest = s;
+    while let Some(open) = rest.find('{') {
+        current.text.push_str(&rest[..open]);
+        let tail = &rest[open..];
+        let escape = tail.find('}').map(|close| &tail[1..close]);
+        let color = match escape {
+            Some("/") => Some(None),
+            Some(code) => code
+                .strip_prefix('c')
+                .filter(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|digits| digits.parse::<usize>().ok())
+                .map(Some),
+            None => None,
+        };
+        match (color, escape) {
+            (Some(color), Some(code)) => {
+                if !current.text.is_empty() {
+                    runs.push(current.clone());
+                }
+                current = ColorRun {
+                    text: String::new(),
+                    color,
+                };
+                rest = &tail[code.len() + 2..];
+            }
+            _ => {
+                current.text.push('{');
+                rest = &tail[1..];
+            }
+        }
+    }
+    current.text.push_str(rest);
+    if !current.text.is_empty() {
+        runs.push(current);
+    }
+    runs
+}
+
+// Tab stops are this many space widths apart, measured from the start x.
+const TAB_STOP_SPACES: i32 = 8;
+
 /*
  * Texture that contains the glyphs rendered from a DiskFont. The backing texture
  * could be shared with other components so we define bounds that can contain
@@ -31,6 +100,11 @@ pub struct FontTexture<'a> {
     // Stencil texture (inverted alpha: glyph pixels transparent, bg opaque white).
     // Wrapped in RefCell so set_color_mod can be called via &self.
     stencil: Option<RefCell<Texture<'a>>>,
+
+    // (src, dst) rect of every glyph laid out for rendering, so tests can
+    // check glyph placement without a GPU.
+    #[cfg(test)]
+    captures: RefCell<Vec<(Rect, Rect)>>,
 }
 
 impl<'a> FontTexture<'a> {
@@ -45,6 +119,8 @@ impl<'a> FontTexture<'a> {
             pixels_32: Vec::new(),
             texture: texture.clone(),
             stencil: None,
+            #[cfg(test)]
+            captures: RefCell::new(Vec::new()),
         };
 
         ft.init_texture();
@@ -103,6 +179,15 @@ impl<'a> FontTexture<'a> {
         }
     }
 
+    /// Set the alpha modulation on the backing font texture, for fading text.
+    /// Call with 255 to reset to fully opaque rendering.
+    pub fn set_alpha_mod(&self, alpha: u8) {
+        if let Some(strong_texture) = self.texture.upgrade() {
+            let mut tex = strong_texture.borrow_mut();
+            tex.set_alpha_mod(alpha);
+        }
+    }
+
     // Draw all the font glyphs into the provided texture within the rect provided
     fn init_texture(&mut self) {
         // build the pixel cache if needed
@@ -143,20 +228,40 @@ impl<'a> FontTexture<'a> {
 
     /// Calculate the pixel width of a rendered string.
     pub fn string_width(&self, s: &str) -> i32 {
-        let cstr = s.as_bytes();
-        let mut width: i32 = 0;
-        for cc in cstr {
-            if *cc >= self.font.lo_char && *cc <= self.font.hi_char {
-                let cc_index = (cc - self.font.lo_char) as usize;
-                let space: i32 = if self.font.is_proportional() {
-                    self.font.char_space[cc_index] as i32
-                } else {
-                    self.font.x_size as i32
-                };
-                width += space;
-            }
+        self.font.string_width(s)
+    }
+
+    /// Pixel offset of a text caret placed before byte `index` of `s`,
+    /// measured from where the string starts, using the same advances as
+    /// `render_string`. Index 0 is 0; an index at or past the end gives the
+    /// full advance width.
+    pub fn caret_x(&self, s: &str, index: usize) -> i32 {
+        let carets = self.caret_offsets(s);
+        carets[index.min(carets.len() - 1)]
+    }
+
+    /// Caret index nearest to pixel offset `x` from the start of `s`, for
+    /// click-to-position; the inverse of `caret_x`. Ties go to the earlier
+    /// index.
+    pub fn index_at_x(&self, s: &str, x: i32) -> usize {
+        self.caret_offsets(s)
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, caret)| (x - **caret).abs())
+            .map_or(0, |(index, _)| index)
+    }
+
+    // Caret offset before each byte of `s`, plus one for the end. Bytes are
+    // laid out one glyph each, as in `layout_string`.
+    fn caret_offsets(&self, s: &str) -> Vec<i32> {
+        let mut carets: Vec<i32> = Vec::with_capacity(s.len() + 1);
+        let mut pen: i32 = 0;
+        carets.push(pen);
+        for cc in s.bytes() {
+            pen += self.advance(cc);
+            carets.push(pen);
         }
-        width
+        carets
     }
 
     // render a string to the given canvas
@@ -176,6 +281,430 @@ impl<'a> FontTexture<'a> {
         }
     }
 
+    /// Render a string scaled by a possibly fractional `scale`. The pen
+    /// position is kept in `f32` and each glyph's destination is rounded on
+    /// its own, so rounding error doesn't build up along long lines. `y` is
+    /// the baseline, as for `render_string`.
+    pub fn render_string_scaled<T: RenderTarget>(
+        &self,
+        s: &str,
+        canvas: &mut Canvas<T>,
+        x: f32,
+        y: f32,
+        scale: f32,
+    ) {
+        if let Some(strong_texture) = self.texture.upgrade() {
+            match strong_texture.try_borrow() {
+                Err(e) => println!("Error borrowing font texture for rendering: {}", e),
+                Ok(tex) => {
+                    let (glyphs, _) = self.layout_string_scaled(s, x, y, scale);
+                    for (src_rect, dst_rect) in glyphs {
+                        canvas.copy(&tex, src_rect, dst_rect).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    // Glyph layout for `render_string_scaled`, plus the final pen position.
+    fn layout_string_scaled(&self, s: &str, x: f32, y: f32, scale: f32) -> (GlyphRects, f32) {
+        let top = (y - self.font.ascent() as f32 * scale).round() as i32;
+        let height = (self.font.y_size as f32 * scale).round() as u32;
+
+        let mut glyphs: GlyphRects = Vec::with_capacity(s.len());
+        let mut pen = x;
+        for cc in s.bytes() {
+            if cc < self.font.lo_char || cc > self.font.hi_char {
+                continue;
+            }
+            let cc_index = (cc - self.font.lo_char) as usize;
+            let cc_loc = self.font.char_loc[cc_index];
+            let (kern, space) = if self.font.is_proportional() {
+                (
+                    self.font.char_kern[cc_index] as i32,
+                    self.font.char_space[cc_index] as f32,
+                )
+            } else {
+                (0, self.font.x_size as f32)
+            };
+
+            if cc_loc.1 > 0 {
+                let left = pen.round() as i32;
+                let right = (pen + cc_loc.1 as f32 * scale).round() as i32;
+                let src_rect = Rect::new(
+                    self.bounds.x + cc_loc.0 as i32 + kern,
+                    self.bounds.y,
+                    cc_loc.1 as u32,
+                    self.font.y_size as u32,
+                );
+                let dst_rect = Rect::new(left, top, (right - left).max(1) as u32, height);
+                glyphs.push((src_rect, dst_rect));
+            }
+            pen += space * scale;
+        }
+
+        #[cfg(test)]
+        self.captures.borrow_mut().extend_from_slice(&glyphs);
+
+        (glyphs, pen)
+    }
+
+    /// Render a string on a fixed grid: each character the font covers takes
+    /// one `cell_width` cell, with its glyph left-aligned in the cell, so a
+    /// proportional font lines up in columns. `char_space` is ignored but the
+    /// kern is still applied to the glyph source.
+    pub fn render_string_monospaced<T: RenderTarget>(
+        &self,
+        s: &str,
+        canvas: &mut Canvas<T>,
+        x: i32,
+        y: i32,
+        cell_width: u32,
+    ) {
+        if let Some(strong_texture) = self.texture.upgrade() {
+            match strong_texture.try_borrow() {
+                Err(e) => println!("Error borrowing font texture for rendering: {}", e),
+                Ok(tex) => {
+                    for (src_rect, dst_rect) in self.layout_monospaced(s, x, y, cell_width) {
+                        canvas.copy(&tex, src_rect, dst_rect).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    // Glyph layout for `render_string_monospaced`.
+    fn layout_monospaced(&self, s: &str, x: i32, y: i32, cell_width: u32) -> GlyphRects {
+        let top = y - self.font.ascent() as i32;
+        let mut glyphs: GlyphRects = Vec::with_capacity(s.len());
+        let mut cell_x = x;
+        for cc in s.bytes() {
+            if cc < self.font.lo_char || cc > self.font.hi_char {
+                continue;
+            }
+            let cc_index = (cc - self.font.lo_char) as usize;
+            let cc_loc = self.font.char_loc[cc_index];
+            let kern = if self.font.is_proportional() {
+                self.font.char_kern[cc_index] as i32
+            } else {
+                0
+            };
+            if cc_loc.1 > 0 {
+                let src_rect = Rect::new(
+                    self.bounds.x + cc_loc.0 as i32 + kern,
+                    self.bounds.y,
+                    cc_loc.1 as u32,
+                    self.font.y_size as u32,
+                );
+                let dst_rect = Rect::new(cell_x, top, cc_loc.1 as u32, self.font.y_size as u32);
+                glyphs.push((src_rect, dst_rect));
+            }
+            cell_x += cell_width as i32;
+        }
+
+        #[cfg(test)]
+        self.captures.borrow_mut().extend_from_slice(&glyphs);
+
+        glyphs
+    }
+
+    /// Render a string containing `{cN}` / `{/}` color escapes (see
+    /// `parse_color_escapes`). Each run is tinted with its palette color; the
+    /// texture's current color mod is the default color and is restored
+    /// afterwards. Indices outside the palette use the default color.
+    pub fn render_string_escaped<T: RenderTarget>(
+        &self,
+        s: &str,
+        canvas: &mut Canvas<T>,
+        x: i32,
+        y: i32,
+        palette: &Palette,
+    ) {
+        let Some(strong_texture) = self.texture.upgrade() else {
+            return;
+        };
+        let saved_mod = strong_texture.borrow().color_mod();
+        let mut pen = x;
+        for run in parse_color_escapes(s) {
+            let (r, g, b) = match run.color.and_then(|index| palette.get_color(index)) {
+                Some(color) => (color.r(), color.g(), color.b()),
+                None => saved_mod,
+            };
+            strong_texture.borrow_mut().set_color_mod(r, g, b);
+            self.render_string(&run.text, canvas, pen, y);
+            pen += self.string_width(&run.text);
+        }
+        strong_texture
+            .borrow_mut()
+            .set_color_mod(saved_mod.0, saved_mod.1, saved_mod.2);
+    }
+
+    /// Pixel width of `s` as `render_string_dir` lays it out, in either
+    /// direction: like `string_width`, but tabs the font lacks advance to the
+    /// next tab stop.
+    pub fn string_width_dir(&self, s: &str) -> i32 {
+        s.bytes().fold(0, |pen, cc| {
+            if cc == b'\t' && (cc < self.font.lo_char || cc > self.font.hi_char) {
+                self.next_tab_stop(pen)
+            } else {
+                pen + self.advance(cc)
+            }
+        })
+    }
+
+    /// Render a string laid out in the given direction. Right-to-left places
+    /// the first character at the rightmost position, ending at `x`, and
+    /// advances leftward with the glyphs themselves unmirrored. Tabs jump to
+    /// tab stops 8 spaces apart in the direction of travel; measure with
+    /// `string_width_dir`.
+    pub fn render_string_dir<T: RenderTarget>(
+        &self,
+        s: &str,
+        canvas: &mut Canvas<T>,
+        x: i32,
+        y: i32,
+        dir: Direction,
+    ) {
+        if let Some(strong_texture) = self.texture.upgrade() {
+            match strong_texture.try_borrow() {
+                Err(e) => println!("Error borrowing font texture for rendering: {}", e),
+                Ok(tex) => {
+                    let dst_h = self.font.y_size as u32;
+                    for (src_rect, dst_rect) in
+                        self.layout_string_dir(s, self.bounds, x, y, dst_h, dir)
+                    {
+                        canvas.copy(&tex, src_rect, dst_rect).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Render a whole line right-to-left for mirrored text: characters are
+    /// processed last to first and the line is right-aligned so the first
+    /// logical character ends at `right`. Glyphs themselves are not mirrored
+    /// and there is no bidi reordering. Tabs count as spaces.
+    pub fn render_line_rtl<T: RenderTarget>(
+        &self,
+        s: &str,
+        canvas: &mut Canvas<T>,
+        right: i32,
+        y: i32,
+    ) {
+        if let Some(strong_texture) = self.texture.upgrade() {
+            match strong_texture.try_borrow() {
+                Err(e) => println!("Error borrowing font texture for rendering: {}", e),
+                Ok(tex) => {
+                    for (src_rect, dst_rect) in self.layout_line_rtl(s, right, y) {
+                        canvas.copy(&tex, src_rect, dst_rect).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    // Glyph layout for `render_line_rtl`, in drawing (visual left to right) order.
+    fn layout_line_rtl(&self, s: &str, right: i32, y: i32) -> GlyphRects {
+        let reversed: String = s
+            .chars()
+            .rev()
+            .map(|c| if c == '\t' { ' ' } else { c })
+            .collect();
+        let left = right - self.font.text_width(s);
+        self.layout_string(&reversed, self.bounds, left, y, self.font.y_size as u32)
+    }
+
+    /// Render consecutive runs of text, each in its own font, on one line.
+    /// `y` is the shared baseline, so fonts of different heights line up on
+    /// it; each run starts where the previous run's advance ended.
+    pub fn render_mixed<T: RenderTarget>(
+        runs: &[(&str, &FontTexture)],
+        canvas: &mut Canvas<T>,
+        x: i32,
+        y: i32,
+    ) {
+        for ((_, font), glyphs) in runs.iter().zip(Self::layout_mixed(runs, x, y)) {
+            if let Some(strong_texture) = font.texture.upgrade() {
+                match strong_texture.try_borrow() {
+                    Err(e) => println!("Error borrowing font texture for rendering: {}", e),
+                    Ok(tex) => {
+                        for (src_rect, dst_rect) in glyphs {
+                            canvas.copy(&tex, src_rect, dst_rect).unwrap();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Glyph layout of each run in `render_mixed`, in run order.
+    fn layout_mixed(runs: &[(&str, &FontTexture)], x: i32, y: i32) -> Vec<GlyphRects> {
+        let mut pen = x;
+        runs.iter()
+            .map(|(text, font)| {
+                let dst_h = font.font.y_size as u32;
+                let glyphs = font.layout_string(text, font.bounds, pen, y, dst_h);
+                pen += font.string_width(text);
+                glyphs
+            })
+            .collect()
+    }
+
+    /// Render `base` with the `accent` glyph drawn above it, for accented
+    /// letters (é, ñ) the font has no single glyph for. The accent's inked
+    /// rows are centered over the base glyph and end one pixel above the
+    /// base's top inked row, or above the ascent line for an empty base.
+    /// If either glyph is missing from the font, the base is drawn alone.
+    pub fn render_with_combining<T: RenderTarget>(
+        &self,
+        base: char,
+        accent: char,
+        canvas: &mut Canvas<T>,
+        x: i32,
+        y: i32,
+    ) {
+        if let Some(strong_texture) = self.texture.upgrade() {
+            match strong_texture.try_borrow() {
+                Err(e) => println!("Error borrowing font texture for rendering: {}", e),
+                Ok(tex) => {
+                    for (src_rect, dst_rect) in self.layout_combining(base, accent, x, y) {
+                        canvas.copy(&tex, src_rect, dst_rect).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    // Glyph layout for `render_with_combining`: the base glyph, then the accent.
+    fn layout_combining(&self, base: char, accent: char, x: i32, y: i32) -> GlyphRects {
+        // the base glyph is placed as layout_string would place it, built
+        // from its font index so bytes past 0x7F aren't re-encoded as UTF-8
+        let Some(base_index) = self.glyph_index(base) else {
+            return Vec::new();
+        };
+        let (base_x, base_w) = self.font.char_loc[base_index];
+        let base_src = Rect::new(
+            self.bounds.x + base_x as i32 + self.kern(base_index),
+            self.bounds.y,
+            base_w as u32,
+            self.font.y_size as u32,
+        );
+        let base_rect = Rect::new(
+            x,
+            y - self.font.ascent() as i32,
+            base_w as u32,
+            self.font.y_size as u32,
+        );
+        let mut glyphs = vec![(base_src, base_rect)];
+
+        #[cfg(test)]
+        self.captures.borrow_mut().push((base_src, base_rect));
+
+        let Some(accent_index) = self.glyph_index(accent) else {
+            return glyphs;
+        };
+        let Some((accent_top, accent_bottom)) = self.glyph_ink_rows(accent_index) else {
+            return glyphs;
+        };
+
+        let base_top = self.glyph_ink_rows(base_index).map_or(0, |(top, _)| top) as i32;
+        let (accent_x, accent_w) = self.font.char_loc[accent_index];
+        let accent_h = (accent_bottom - accent_top + 1) as u32;
+        // offset the source by the kern, as layout_string does
+        let src_rect = Rect::new(
+            self.bounds.x + accent_x as i32 + self.kern(accent_index),
+            self.bounds.y + accent_top as i32,
+            accent_w as u32,
+            accent_h,
+        );
+        let dst_rect = Rect::new(
+            base_rect.x() + (base_rect.width() as i32 - accent_w as i32) / 2,
+            base_rect.y() + base_top - 1 - accent_h as i32,
+            accent_w as u32,
+            accent_h,
+        );
+        glyphs.push((src_rect, dst_rect));
+
+        #[cfg(test)]
+        self.captures.borrow_mut().push((src_rect, dst_rect));
+
+        glyphs
+    }
+
+    // Index into the font tables for `c`, if the font has a visible glyph for it.
+    fn glyph_index(&self, c: char) -> Option<usize> {
+        let cc = u8::try_from(c).ok()?;
+        if cc < self.font.lo_char || cc > self.font.hi_char {
+            return None;
+        }
+        let index = (cc - self.font.lo_char) as usize;
+        (self.font.char_loc[index].1 > 0).then_some(index)
+    }
+
+    // First and last rows of the glyph that contain any set pixels.
+    fn glyph_ink_rows(&self, index: usize) -> Option<(usize, usize)> {
+        let (loc, width) = self.font.char_loc[index];
+        let inked = |row: &usize| {
+            let start = row * self.font.modulo + loc;
+            self.font.char_data[start..start + width]
+                .iter()
+                .any(|&px| px != 0)
+        };
+        let top = (0..self.font.y_size).find(inked)?;
+        let bottom = (0..self.font.y_size).rfind(inked)?;
+        Some((top, bottom))
+    }
+
+    /// Render a string with a drop shadow: first in `shadow` color shifted by
+    /// `offset`, then in the texture's current color on top. The texture's
+    /// color mod is restored afterwards.
+    pub fn render_string_shadowed<T: RenderTarget>(
+        &self,
+        s: &str,
+        canvas: &mut Canvas<T>,
+        x: i32,
+        y: i32,
+        shadow: Color,
+        offset: (i32, i32),
+    ) {
+        if let Some(strong_texture) = self.texture.upgrade() {
+            let (shadow_glyphs, glyphs) = self.layout_shadowed(s, x, y, offset);
+            let saved_mod = strong_texture.borrow().color_mod();
+
+            strong_texture
+                .borrow_mut()
+                .set_color_mod(shadow.r, shadow.g, shadow.b);
+            if let Ok(tex) = strong_texture.try_borrow() {
+                for (src_rect, dst_rect) in shadow_glyphs {
+                    canvas.copy(&tex, src_rect, dst_rect).unwrap();
+                }
+            }
+
+            strong_texture
+                .borrow_mut()
+                .set_color_mod(saved_mod.0, saved_mod.1, saved_mod.2);
+            if let Ok(tex) = strong_texture.try_borrow() {
+                for (src_rect, dst_rect) in glyphs {
+                    canvas.copy(&tex, src_rect, dst_rect).unwrap();
+                }
+            }
+        }
+    }
+
+    // Glyph layouts for the shadow pass and the text pass, in drawing order.
+    fn layout_shadowed(
+        &self,
+        s: &str,
+        x: i32,
+        y: i32,
+        offset: (i32, i32),
+    ) -> (GlyphRects, GlyphRects) {
+        let dst_h = self.font.y_size as u32;
+        let shadow = self.layout_string(s, self.bounds, x + offset.0, y + offset.1, dst_h);
+        let text = self.layout_string(s, self.bounds, x, y, dst_h);
+        (shadow, text)
+    }
+
     /// Render a string with a solid background color, matching Amiga JAM2 mode.
     ///
     /// Amiga `Text()` in JAM2 fills the entire character cell rectangle
@@ -198,7 +727,7 @@ impl<'a> FontTexture<'a> {
         // Pass 1: filled rectangle for the full string extent (JAM2 background).
         let total_w = self.string_width(s);
         if total_w > 0 {
-            let y_top = y - self.font.baseline as i32;
+            let y_top = y - self.font.ascent() as i32;
             let bg_rect = Rect::new(x, y_top, total_w as u32, self.font.y_size as u32);
             canvas.set_draw_color(sdl3::pixels::Color::RGB(bg.0, bg.1, bg.2));
             canvas.fill_rect(bg_rect).unwrap();
@@ -225,24 +754,121 @@ impl<'a> FontTexture<'a> {
         x: i32,
         y: i32,
     ) {
+        let glyphs = self.layout_string(s, src_origin, x, y, self.font.y_size as u32);
+        for (src_rect, glyph_rect) in glyphs {
+            canvas.copy(texture, src_rect, glyph_rect).unwrap();
+        }
+    }
+
+    /// Compute the (src, dst) rect of each visible glyph in `s`.
+    /// `src_origin` is the top-left offset into the texture where glyph data
+    /// starts; `dst_height` is the rendered glyph height (y_size, or 2× for hires).
+    fn layout_string(
+        &self,
+        s: &str,
+        src_origin: Rect,
+        x: i32,
+        y: i32,
+        dst_height: u32,
+    ) -> Vec<(Rect, Rect)> {
+        self.layout_glyphs(s, src_origin, (x, y), dst_height, Direction::LeftToRight, false)
+    }
+
+    // Distance between tab stops, based on the width of a space.
+    fn tab_width(&self) -> i32 {
+        let space = self.string_width(" ");
+        TAB_STOP_SPACES * if space > 0 { space } else { self.font.x_size as i32 }
+    }
+
+    // Kern offset of the glyph at font table `index`; only proportional fonts kern.
+    fn kern(&self, index: usize) -> i32 {
+        if self.font.is_proportional() {
+            self.font.char_kern[index] as i32
+        } else {
+            0
+        }
+    }
+
+    // Horizontal advance of byte `cc`, 0 if the font doesn't cover it.
+    fn advance(&self, cc: u8) -> i32 {
+        if cc < self.font.lo_char || cc > self.font.hi_char {
+            0
+        } else if self.font.is_proportional() {
+            self.font.char_space[(cc - self.font.lo_char) as usize] as i32
+        } else {
+            self.font.x_size as i32
+        }
+    }
+
+    // Pen position after a tab at `pen`, measured from the start x.
+    fn next_tab_stop(&self, pen: i32) -> i32 {
+        let tab = self.tab_width();
+        (pen / tab + 1) * tab
+    }
+
+    /// Layout for `render_string_dir`. For right-to-left, `x` is where the
+    /// first glyph's advance ends and each advance moves leftward. Tabs (when
+    /// not part of the font) jump to the next stop in the same direction.
+    fn layout_string_dir(
+        &self,
+        s: &str,
+        src_origin: Rect,
+        x: i32,
+        y: i32,
+        dst_height: u32,
+        dir: Direction,
+    ) -> Vec<(Rect, Rect)> {
+        self.layout_glyphs(s, src_origin, (x, y), dst_height, dir, true)
+    }
+
+    // Shared glyph layout. With `tab_stops`, tabs the font lacks advance to
+    // the next tab stop; otherwise they are skipped like any other missing
+    // character.
+    fn layout_glyphs(
+        &self,
+        s: &str,
+        src_origin: Rect,
+        (x, y): (i32, i32),
+        dst_height: u32,
+        dir: Direction,
+        tab_stops: bool,
+    ) -> Vec<(Rect, Rect)> {
         let cstr = s.as_bytes();
-        let y_adjusted = y - self.font.baseline as i32;
-        let mut glyph_rect = Rect::new(x, y_adjusted, 0, self.font.y_size as u32);
+
+        // y coordinate is for the baseline of the font, so adjust for that
+        let y_adjusted = y - self.font.ascent() as i32;
+
+        let mut glyphs: Vec<(Rect, Rect)> = Vec::with_capacity(cstr.len());
+        let mut glyph_rect = Rect::new(x, y_adjusted, 0, dst_height);
+        // pen position, measured from x in the direction of travel
+        let mut pen: i32 = 0;
         for cc in cstr {
-            if *cc >= self.font.lo_char && *cc <= self.font.hi_char {
+            let in_font = *cc >= self.font.lo_char && *cc <= self.font.hi_char;
+            if tab_stops && *cc == b'\t' && !in_font {
+                pen = self.next_tab_stop(pen);
+                continue;
+            }
+            if in_font {
                 let cc_index = (cc - self.font.lo_char) as usize;
                 let cc_loc = self.font.char_loc[cc_index];
-                let kern: i32 = if self.font.is_proportional() {
-                    self.font.char_kern[cc_index] as i32
-                } else {
-                    0
-                };
+
+                let kern = self.kern(cc_index);
                 let space: i32 = if self.font.is_proportional() {
                     self.font.char_space[cc_index] as i32
                 } else {
                     self.font.x_size as i32
                 };
+
+                // RTL steps back over the advance before drawing
+                let cell_x = match dir {
+                    Direction::LeftToRight => x + pen,
+                    Direction::RightToLeft => x - pen - space,
+                };
+                glyph_rect.set_x(cell_x);
+
+                // Don't do anything for spaces, just skip ahead to the next coordinates
                 if cc_loc.1 > 0 {
+                    // grab glyph width and adjust glyph_rect, making sure to adjust the origin to our shared texture bounds
                     glyph_rect.set_width(cc_loc.1 as u32);
                     let src_rect = Rect::new(
                         src_origin.x + cc_loc.0 as i32 + kern,
@@ -250,13 +876,24 @@ impl<'a> FontTexture<'a> {
                         cc_loc.1 as u32,
                         self.font.y_size as u32,
                     );
-                    canvas
-                        .copy(texture, src_rect, glyph_rect)
-                        .unwrap();
+                    glyphs.push((src_rect, glyph_rect));
                 }
-                glyph_rect.set_x(glyph_rect.x() + space);
+
+                // advance to the next glyph location
+                pen += space;
             }
         }
+
+        #[cfg(test)]
+        self.captures.borrow_mut().extend_from_slice(&glyphs);
+
+        glyphs
+    }
+
+    /// Take the glyph rects recorded since the last call.
+    #[cfg(test)]
+    pub fn take_captures(&self) -> Vec<(Rect, Rect)> {
+        std::mem::take(&mut *self.captures.borrow_mut())
     }
 
     /*
@@ -276,49 +913,7 @@ impl<'a> FontTexture<'a> {
         x: i32,
         y: i32,
     ) {
-        let cstr = s.as_bytes();
-
-        // y coordinate is for the baseline of the font, so adjust for that
-        let y_adjusted = y - self.font.baseline as i32;
-
-        let mut glyph_rect = Rect::new(x, y_adjusted, 0, self.font.y_size as u32);
-        for cc in cstr {
-            if *cc >= self.font.lo_char && *cc <= self.font.hi_char {
-                let cc_index = (cc - self.font.lo_char) as usize;
-                let cc_loc = self.font.char_loc[cc_index];
-
-                let kern: i32 = if self.font.is_proportional() {
-                    self.font.char_kern[cc_index] as i32
-                } else {
-                    0
-                };
-                let space: i32 = if self.font.is_proportional() {
-                    self.font.char_space[cc_index] as i32
-                } else {
-                    self.font.x_size as i32
-                };
-
-                // Don't do anything for spaces, just skip ahead to the next coordinates
-                if cc_loc.1 > 0 {
-                    // grab glyph width and adjust glyph_rect, making sure to adjust the origin to our shared texture bounds
-                    glyph_rect.set_width(cc_loc.1 as u32);
-                    let src_rect = Rect::new(
-                        self.bounds.x + cc_loc.0 as i32 + kern,
-                        self.bounds.y,
-                        cc_loc.1 as u32,
-                        self.font.y_size as u32,
-                    );
-
-                    // copy the glyph
-                    canvas
-                        .copy(texture, src_rect, glyph_rect)
-                        .unwrap();
-                }
-
-                // advance to the next glyph location
-                glyph_rect.set_x(glyph_rect.x() + space);
-            }
-        }
+        self.render_string_with_texture(s, canvas, texture, self.bounds, x, y);
     }
 
     /// Render a string with glyphs stretched to 2× height (title screen style).
@@ -348,38 +943,264 @@ impl<'a> FontTexture<'a> {
         x: i32,
         y: i32,
     ) {
-        let cstr = s.as_bytes();
-        let y_adjusted = y - self.font.baseline as i32;
         let dst_h = (self.font.y_size * 2) as u32;
-        let mut dst_rect = Rect::new(x, y_adjusted, 0, dst_h);
-        for cc in cstr {
-            if *cc >= self.font.lo_char && *cc <= self.font.hi_char {
-                let cc_index = (cc - self.font.lo_char) as usize;
-                let cc_loc = self.font.char_loc[cc_index];
-                let kern: i32 = if self.font.is_proportional() {
-                    self.font.char_kern[cc_index] as i32
-                } else {
-                    0
-                };
-                let space: i32 = if self.font.is_proportional() {
-                    self.font.char_space[cc_index] as i32
-                } else {
-                    self.font.x_size as i32
-                };
-                if cc_loc.1 > 0 {
-                    dst_rect.set_width(cc_loc.1 as u32);
-                    let src_rect = Rect::new(
-                        self.bounds.x + cc_loc.0 as i32 + kern,
-                        self.bounds.y,
-                        cc_loc.1 as u32,
-                        self.font.y_size as u32,
-                    );
-                    canvas
-                        .copy(texture, src_rect, dst_rect)
-                        .unwrap();
-                }
-                dst_rect.set_x(dst_rect.x() + space);
+        for (src_rect, dst_rect) in self.layout_string(s, self.bounds, x, y, dst_h) {
+            canvas.copy(texture, src_rect, dst_rect).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::font::FPF_PROPORTIONAL;
+
+    use std::rc::Weak;
+
+    // Proportional font covering 'a'..='c' with distinct advances and a kern.
+    fn test_font_texture() -> FontTexture<'static> {
+        let mut font = DiskFont::new();
+        font.y_size = 8;
+        font.baseline = 6;
+        font.flags = FPF_PROPORTIONAL;
+        font.lo_char = b'a';
+        font.hi_char = b'c';
+        font.modulo = 24;
+        font.char_data = vec![0; 24 * 8];
+        font.char_loc = vec![(0, 5), (5, 6), (11, 4)];
+        font.char_space = vec![6, 7, 5];
+        font.char_kern = vec![0, 1, 0];
+        FontTexture::new(&font, &Rect::new(100, 200, 24, 8), Weak::new())
+    }
+
+    #[test]
+    fn test_parse_color_escapes() {
+        let runs = parse_color_escapes("a {c3}orange{c1}white{/} plain");
+        let expected = [
+            ("a ", None),
+            ("orange", Some(3)),
+            ("white", Some(1)),
+            (" plain", None),
+        ];
+        assert_eq!(runs.len(), expected.len());
+        for (run, (text, color)) in runs.iter().zip(expected) {
+            assert_eq!((run.text.as_str(), run.color), (text, color));
+        }
+
+        // unknown or unterminated escapes are literal text
+        let runs = parse_color_escapes("{x}{c}{c12{cZ}");
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].text, "{x}{c}{c12{cZ}");
+        assert!(parse_color_escapes("{c2}").is_empty());
+    }
+
+    #[test]
+    fn test_layout_records_glyph_rects() {
+        let ft = test_font_texture();
+        ft.layout_string("abc", *ft.get_bounds(), 10, 20, 8);
+        let captures = ft.take_captures();
+        assert_eq!(captures.len(), 3);
+
+        let dst: Vec<(i32, i32, u32)> = captures
+            .iter()
+            .map(|(_, d)| (d.x(), d.y(), d.width()))
+            .collect();
+        // advances are 6 then 7; y is shifted up by the baseline
+        assert_eq!(dst, vec![(10, 14, 5), (16, 14, 6), (23, 14, 4)]);
+
+        let src: Vec<(i32, i32, u32)> = captures
+            .iter()
+            .map(|(s, _)| (s.x(), s.y(), s.width()))
+            .collect();
+        assert_eq!(src, vec![(100, 200, 5), (106, 200, 6), (111, 200, 4)]);
+
+        assert!(ft.take_captures().is_empty());
+    }
+
+    #[test]
+    fn test_mixed_runs_share_baseline() {
+        let small = test_font_texture();
+
+        let mut font = small.get_font().clone();
+        font.y_size = 12;
+        font.baseline = 9;
+        font.char_data = vec![0; 24 * 12];
+        let tall = FontTexture::new(&font, &Rect::new(0, 0, 24, 12), Weak::new());
+        assert_eq!((tall.get_font().ascent(), tall.get_font().descent()), (9, 3));
+
+        let runs = FontTexture::layout_mixed(&[("ab", &small), ("ca", &tall)], 10, 30);
+        assert_eq!(runs.len(), 2);
+
+        for (glyphs, font) in runs.iter().zip([&small, &tall]) {
+            for (_, dst) in glyphs {
+                // glyph top + ascent lands on the shared baseline
+                assert_eq!(dst.y() + font.get_font().ascent() as i32, 30);
+                assert_eq!(dst.height(), font.get_font().y_size as u32);
             }
         }
+        // second run starts after the first run's advances (6 + 7)
+        assert_eq!(runs[1][0].1.x(), 23);
+    }
+
+    #[test]
+    fn test_combining_accent_sits_above_base() {
+        let mut font = test_font_texture().get_font().clone();
+        // 'a' (cols 0..5) inked on rows 3..=7, 'c' (cols 11..15) on row 1 only
+        for row in 3..8 {
+            font.char_data[row * 24..row * 24 + 5].fill(0xFF);
+        }
+        font.char_data[24 + 12..24 + 14].fill(0xFF);
+        let ft = FontTexture::new(&font, &Rect::new(100, 200, 24, 8), Weak::new());
+
+        let glyphs = ft.layout_combining('a', 'c', 10, 20);
+        assert_eq!(glyphs.len(), 2);
+        let (base_src, base_dst) = glyphs[0];
+        let (accent_src, accent_dst) = glyphs[1];
+        assert_eq!((base_src.x(), base_dst.x(), base_dst.y()), (100, 10, 14));
+
+        // only the accent's inked row is copied, ending above the base's ink
+        assert_eq!((accent_src.x(), accent_src.y(), accent_src.height()), (111, 201, 1));
+        assert!(accent_dst.bottom() <= base_dst.y() + 3);
+        assert_eq!((accent_dst.x(), accent_dst.y()), (10, 15));
+
+        // a kerned accent ('b', kern 1) is offset like layout_string
+        font.char_data[6..8].fill(0xFF);
+        let ft = FontTexture::new(&font, &Rect::new(100, 200, 24, 8), Weak::new());
+        let kerned = ft.layout_combining('a', 'b', 10, 20);
+        let plain = ft.layout_string("b", *ft.get_bounds(), 10, 20, 8);
+        assert_eq!(kerned[1].0.x(), plain[0].0.x());
+        assert_eq!(kerned[1].0.x(), 106);
+
+        // a missing accent falls back to the base glyph alone
+        assert_eq!(ft.layout_combining('a', 'é', 10, 20), vec![glyphs[0]]);
+        assert!(ft.layout_combining('z', 'c', 10, 20).is_empty());
+
+        // Latin-1 bases past 0x7F are a single glyph, not a UTF-8 sequence
+        font.lo_char = 0xE7;
+        font.hi_char = 0xE9;
+        let ft = FontTexture::new(&font, &Rect::new(100, 200, 24, 8), Weak::new());
+        let glyphs = ft.layout_combining('é', 'z', 10, 20);
+        assert_eq!(glyphs, vec![(Rect::new(111, 200, 4, 8), Rect::new(10, 14, 4, 8))]);
+    }
+
+    #[test]
+    fn test_scaled_layout_does_not_accumulate_rounding() {
+        let ft = test_font_texture();
+        let text = "abcabcabcabcabcabcabcabcabcabc";
+        let scale = 1.37;
+        let (glyphs, end) = ft.layout_string_scaled(text, 3.0, 20.0, scale);
+        assert_eq!(glyphs.len(), text.len());
+
+        let expected = 3.0 + ft.string_width(text) as f32 * scale;
+        assert!((end - expected).abs() < 0.01, "{end} vs {expected}");
+
+        // every glyph starts at its exact scaled pen position, rounded once
+        let mut pen = 0;
+        for (cc, (_, dst)) in text.bytes().zip(&glyphs) {
+            let exact = 3.0 + pen as f32 * scale;
+            assert!((dst.x() as f32 - exact).abs() <= 0.5);
+            pen += ft.string_width(&(cc as char).to_string());
+        }
+        assert_eq!(glyphs[0].1.y(), (20.0 - 6.0 * scale).round() as i32);
+        assert_eq!(glyphs[0].1.height(), 11);
+    }
+
+    #[test]
+    fn test_caret_positions_round_trip() {
+        let ft = test_font_texture();
+        let text = "abcab";
+        // advances are a=6, b=7, c=5
+        let carets: Vec<i32> = (0..=text.len()).map(|i| ft.caret_x(text, i)).collect();
+        assert_eq!(carets, vec![0, 6, 13, 18, 24, 31]);
+        assert_eq!(ft.caret_x(text, 99), ft.string_width(text));
+
+        for (index, caret) in carets.iter().enumerate() {
+            assert_eq!(ft.index_at_x(text, *caret), index);
+            // a click just either side of a caret still finds it
+            assert_eq!(ft.index_at_x(text, caret + 1), index);
+            assert_eq!(ft.index_at_x(text, caret - 1), index);
+        }
+        assert_eq!(ft.index_at_x(text, -50), 0);
+        assert_eq!(ft.index_at_x(text, 500), text.len());
+
+        // carets line up with where rendering places each glyph
+        let glyphs = ft.layout_string(text, *ft.get_bounds(), 0, 20, 8);
+        for (index, (_, dst)) in glyphs.iter().enumerate() {
+            assert_eq!(dst.x(), ft.caret_x(text, index));
+        }
+        assert_eq!(ft.caret_x("", 0), 0);
+        // tabs the font lacks take no room, as in render_string
+        assert_eq!(ft.caret_x("a\tb", 2), 6);
+        assert_eq!(ft.caret_x("a\tb", 3), ft.string_width("a\tb"));
+    }
+
+    #[test]
+    fn test_shadow_drawn_first_at_offset() {
+        let ft = test_font_texture();
+        ft.layout_shadowed("ab", 10, 20, (1, 2));
+        let captures = ft.take_captures();
+        let dst: Vec<(i32, i32)> = captures.iter().map(|(_, d)| (d.x(), d.y())).collect();
+        // shadow pass shifted by (1, 2), then the text pass on top
+        assert_eq!(dst, vec![(11, 16), (17, 16), (10, 14), (16, 14)]);
+    }
+
+    #[test]
+    fn test_monospaced_layout_uses_fixed_cells() {
+        let ft = test_font_texture();
+        let glyphs = ft.layout_monospaced("abcab", 10, 20, 9);
+        let dst_x: Vec<i32> = glyphs.iter().map(|(_, d)| d.x()).collect();
+        assert_eq!(dst_x.len(), 5);
+        for (ii, xx) in dst_x.iter().enumerate() {
+            assert_eq!((xx - 10) % 9, 0);
+            assert_eq!(xx - 10, ii as i32 * 9);
+        }
+        // glyph sources are unchanged, kern included
+        assert_eq!(glyphs[1].0.x(), 106);
+    }
+
+    #[test]
+    fn test_rtl_layout_starts_at_right_edge() {
+        let ft = test_font_texture();
+        let glyphs = ft.layout_string_dir("abc", *ft.get_bounds(), 40, 20, 8, Direction::RightToLeft);
+        let dst_x: Vec<i32> = glyphs.iter().map(|(_, d)| d.x()).collect();
+        // 'a' (advance 6) ends at x=40, then 'b' (7) and 'c' (5) to its left
+        assert_eq!(dst_x, vec![34, 27, 22]);
+        assert_eq!(dst_x.iter().max(), Some(&dst_x[0]));
+
+        // mirrors the LTR extent: 18 px of advances either way
+        let ltr = ft.layout_string_dir("abc", *ft.get_bounds(), 22, 20, 8, Direction::LeftToRight);
+        assert_eq!(ltr[0].1.x(), 22);
+    }
+
+    #[test]
+    fn test_rtl_line_reverses_characters() {
+        let ft = test_font_texture();
+        let glyphs = ft.layout_line_rtl("abc", 40, 20);
+        let placed: Vec<(i32, i32)> = glyphs.iter().map(|(s, d)| (s.x(), d.x())).collect();
+        // drawn left to right as 'c', 'b', 'a'; 'a' (advance 6) ends at 40
+        assert_eq!(placed, vec![(111, 22), (106, 27), (100, 34)]);
+        let rightmost = glyphs.iter().max_by_key(|(_, d)| d.x()).unwrap();
+        assert_eq!(rightmost.0.x(), 100); // first logical character
+    }
+
+    #[test]
+    fn test_tabs_advance_in_layout_direction() {
+        // monospace with no space glyph, so tab stops are 8 * x_size = 32 px
+        let mut font = test_font_texture().get_font().clone();
+        font.flags = 0;
+        font.x_size = 4;
+        let ft = FontTexture::new(&font, &Rect::new(0, 0, 24, 8), Weak::new());
+
+        let ltr = ft.layout_string_dir("a\tb", *ft.get_bounds(), 10, 8, 8, Direction::LeftToRight);
+        assert_eq!((ltr[0].1.x(), ltr[1].1.x()), (10, 10 + 32));
+
+        let rtl = ft.layout_string_dir("a\tb", *ft.get_bounds(), 100, 8, 8, Direction::RightToLeft);
+        assert_eq!((rtl[0].1.x(), rtl[1].1.x()), (96, 100 - 32 - 4));
+        assert_eq!(ft.string_width_dir("a\tb"), 32 + 4);
+
+        // plain left-to-right layout skips tabs the font lacks, as string_width does
+        let plain = ft.layout_string("a\tb", *ft.get_bounds(), 10, 8, 8);
+        assert_eq!((plain[0].1.x(), plain[1].1.x()), (10, 14));
+        assert_eq!(ft.string_width("a\tb"), 8);
     }
 }
@@ -1,9 +1,14 @@
-use std::time::Instant;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::game::day_phase::DayPhase;
 
 /**
  * This struct manages the game clock, including launch time and game time.
  *
  * The game wall clock is based on a 24,000 tick day cycle, with specific phases.
+ * The day length can be overridden per clock with `set_day_length_ticks`.
  */
 #[derive(Debug)]
 pub struct GameClock {
@@ -16,6 +21,61 @@ pub struct GameClock {
 
     pub game_ticks: u64, // number of game ticks passed total, resets on death/start
     pub paused: bool,
+
+    day_ticks: u64,       // ticks per full day, DAY_TICKS unless overridden
+    max_delta_ticks: u64, // cap on game ticks added by a single update()
+
+    hour_listener: HourListener,
+    pending_events: VecDeque<TimeEvent>, // queued by update()/rest(), drained by poll_events()
+    time_source: TimeSource,
+}
+
+/// Something that happened to the game clock, as reported by `poll_events`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeEvent {
+    /// A new game day began; carries the new day count.
+    DayRollover(u64),
+    /// A day phase began; `event_id` is the fmain.c narrator event (28..=31).
+    Phase { phase: DayPhase, event_id: u8 },
+    /// A new hour began; carries the hour of day (0..=23).
+    Hour(u32),
+}
+
+/// Callback invoked with the new hour of day (0..=23).
+pub type HourCallback = Box<dyn FnMut(u32)>;
+
+// Holds the registered hourly callback so GameClock can still derive Debug.
+#[derive(Default)]
+struct HourListener(Option<HourCallback>);
+
+impl std::fmt::Debug for HourListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0.is_some() {
+            "HourListener(Some)"
+        } else {
+            "HourListener(None)"
+        })
+    }
+}
+
+/// Supplies the current time to a `GameClock`; `Instant::now` unless a test
+/// scripts it.
+pub type NowFn = Box<dyn FnMut() -> Instant>;
+
+// Holds the time source so GameClock can still derive Debug; in a RefCell so
+// `&self` queries like `real_elapsed` can call it.
+struct TimeSource(RefCell<NowFn>);
+
+impl TimeSource {
+    fn now(&self) -> Instant {
+        (self.0.borrow_mut())()
+    }
+}
+
+impl std::fmt::Debug for TimeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TimeSource")
+    }
 }
 
 /*
@@ -27,29 +87,29 @@ struct GameTicker {
     accumulated_nanos: u128,
 }
 const NANOS_PER_TICK: u128 = 33_333_334; // nanoseconds per tick (30 Hz — NTSC interlaced frame rate)
+const DEFAULT_MAX_DELTA_TICKS: u64 = 150; // 5 seconds of game time per update()
 
 impl GameTicker {
-    pub fn new() -> GameTicker {
+    pub fn new(now: Instant) -> GameTicker {
         GameTicker {
-            last_update: Instant::now(),
+            last_update: now,
             accumulated_nanos: 0,
         }
     }
 
-    pub fn update(&mut self) {
-        let now = Instant::now();
-        let elapsed = now.duration_since(self.last_update).as_nanos();
+    pub fn update(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_update).as_nanos();
         self.accumulated_nanos += elapsed;
         self.last_update = now;
     }
 
-    pub fn reset(&mut self) {
-        self.last_update = Instant::now();
+    pub fn reset(&mut self, now: Instant) {
+        self.last_update = now;
         self.accumulated_nanos = 0;
     }
 
-    pub fn get_elapsed_ticks(&mut self) -> u64 {
-        self.update();
+    pub fn get_elapsed_ticks(&mut self, now: Instant) -> u64 {
+        self.update(now);
         let ticks = (self.accumulated_nanos / NANOS_PER_TICK) as u64;
         self.accumulated_nanos %= NANOS_PER_TICK;
         ticks
@@ -99,18 +159,117 @@ impl GameTicker {
    or 800 seconds (13 minutes 20 seconds) of real time. Each hour is 1000 ticks (33.3 seconds).
 */
 
+const DAY_TICKS: u64 = 24_000; // ticks per full day cycle (fmain.c daynight wrap)
+const HOUR_TICKS: u64 = 1_000; // ticks per game hour
+const HOURS_PER_DAY: u64 = 24;
+const PERIODS_PER_DAY: u64 = 12; // dayperiod buckets per day (daynight / 2000)
+const MAX_PENDING_EVENTS: usize = 256; // oldest events are dropped past this if nobody polls
+
+/// Map a dayperiod bucket (0..=11) to the phase it begins, if any. Only the
+/// four buckets that fire narrator events (28..=31) start a phase.
+fn phase_starting_at(period: u64) -> Option<DayPhase> {
+    match period {
+        0 => Some(DayPhase::Midnight),
+        4 => Some(DayPhase::Morning),
+        6 => Some(DayPhase::Midday),
+        9 => Some(DayPhase::Evening),
+        _ => None,
+    }
+}
+
+/// The fmain.c narrator event fired when `phase` begins.
+fn phase_event_id(phase: DayPhase) -> u8 {
+    match phase {
+        DayPhase::Midnight => 28,
+        DayPhase::Morning => 29,
+        DayPhase::Midday => 30,
+        DayPhase::Evening => 31,
+    }
+}
+
 impl GameClock {
     pub fn new() -> GameClock {
+        GameClock::new_with_clock(Box::new(Instant::now))
+    }
+
+    /**
+     * Build a clock that reads the current time from `now_fn` instead of
+     * `Instant::now`, so tests can script exactly how much time passes
+     * between updates.
+     */
+    pub fn new_with_clock(mut now_fn: NowFn) -> GameClock {
+        let start = now_fn();
         GameClock {
-            ticker: GameTicker::new(),
-            mono_base: Instant::now(),
+            ticker: GameTicker::new(start),
+            mono_base: start,
             mono_ticks: 0,
             last_mono_ticks: 0,
             game_ticks: 0,
             paused: false,
+            day_ticks: DAY_TICKS,
+            max_delta_ticks: DEFAULT_MAX_DELTA_TICKS,
+            hour_listener: HourListener::default(),
+            pending_events: VecDeque::new(),
+            time_source: TimeSource(RefCell::new(now_fn)),
         }
     }
 
+    /**
+     * Build a clock from the original save-file encoding: elapsed day count
+     * plus the `daynight` counter. Out of range `daynight` values wrap into
+     * the following day(s), as the original counter would.
+     */
+    pub fn from_original_save(days: u16, daynight: u16) -> GameClock {
+        let mut clock = GameClock::new();
+        clock.game_ticks = days as u64 * DAY_TICKS + daynight as u64;
+        clock
+    }
+
+    /**
+     * Encode the game time the way the original save files do, as
+     * (elapsed days, daynight). The day count saturates at `u16::MAX`, and
+     * `daynight` is rescaled to the original 24000 tick day.
+     */
+    pub fn to_original_save(&self) -> (u16, u16) {
+        let days = self.get_game_days().min(u16::MAX as u64) as u16;
+        (days, (self.daynight() * DAY_TICKS / self.day_ticks) as u16)
+    }
+
+    /**
+     * Override the length of a game day in ticks for this clock; hours and
+     * dayperiods are derived from it. The length is rounded down to a whole
+     * number of hours, with a minimum of one tick per hour, so zero is never
+     * used. The current day count and time of day are preserved.
+     */
+    pub fn set_day_length_ticks(&mut self, ticks: u64) {
+        let ticks = (ticks / HOURS_PER_DAY).max(1) * HOURS_PER_DAY;
+        let daynight = self.daynight() * ticks / self.day_ticks;
+        self.game_ticks = self.get_game_days() * ticks + daynight;
+        self.day_ticks = ticks;
+    }
+
+    /// Ticks per full day for this clock.
+    pub fn day_length_ticks(&self) -> u64 {
+        self.day_ticks
+    }
+
+    fn hour_ticks(&self) -> u64 {
+        self.day_ticks / HOURS_PER_DAY
+    }
+
+    fn period_ticks(&self) -> u64 {
+        self.day_ticks / PERIODS_PER_DAY
+    }
+
+    /**
+     * Cap the game ticks a single `update()` may add, so a long stall such as
+     * the machine sleeping doesn't fast-forward the game by hours. Time past
+     * the cap is discarded. Defaults to 5 seconds worth of ticks.
+     */
+    pub fn set_max_delta_ticks_per_update(&mut self, ticks: u64) {
+        self.max_delta_ticks = ticks;
+    }
+
     /**
      * Update the game clock, calculating elapsed ticks since last update.
      * Call this periodically to keep the clock accurate, generally once per frame.
@@ -118,7 +277,8 @@ impl GameClock {
      */
     pub fn update(&mut self) -> u32 {
         // always update mono ticks, since Instant is monotonic, this is easy
-        let mono_duration = Instant::now().duration_since(self.mono_base).as_nanos();
+        let now = self.time_source.now();
+        let mono_duration = now.saturating_duration_since(self.mono_base).as_nanos();
         self.mono_ticks = (mono_duration / NANOS_PER_TICK) as u64;
 
         let delta = (self.mono_ticks - self.last_mono_ticks) as u32;
@@ -127,22 +287,266 @@ impl GameClock {
         if self.paused {
             return delta;
         }
-        self.ticker.update();
-
-        let elapsed_ticks = self.ticker.get_elapsed_ticks();
+        // anything past the cap (e.g. a resume from system sleep) is dropped
+        let elapsed_ticks = self.ticker.get_elapsed_ticks(now).min(self.max_delta_ticks);
         if elapsed_ticks > 0 {
+            let before = self.game_ticks;
             self.game_ticks += elapsed_ticks;
+            self.notify_hours(before);
         }
 
         delta
     }
 
+    /**
+     * Real (wall clock) time since the clock was created. Unlike the tick
+     * counters this needs no `update()` call, and keeps running while the
+     * game is paused or time is frozen.
+     */
+    pub fn real_elapsed(&self) -> Duration {
+        self.time_source
+            .now()
+            .saturating_duration_since(self.mono_base)
+    }
+
+    /**
+     * Real time elapsed since `past`, or zero if `past` is in the future.
+     */
+    pub fn real_since(&self, past: Instant) -> Duration {
+        self.time_source.now().saturating_duration_since(past)
+    }
+
+    /**
+     * In-game time elapsed since a stored `game_ticks` stamp, broken down as
+     * (days, hours, minutes). A stamp later than the current time (e.g. from
+     * before a reset) yields all zeros.
+     */
+    pub fn elapsed_since(&self, past_game_ticks: u64) -> (u64, u32, u32) {
+        let elapsed = self.game_ticks.saturating_sub(past_game_ticks);
+        let days = elapsed / self.day_ticks;
+        let hours = (elapsed % self.day_ticks) / self.hour_ticks();
+        let minutes = (elapsed % self.hour_ticks()) * 60 / self.hour_ticks();
+        (days, hours as u32, minutes as u32)
+    }
+
+    /**
+     * Same as `update()`, but also reports whether at least one new game day
+     * began during this update.
+     */
+    pub fn update_with_day_rollover(&mut self) -> (u32, bool) {
+        let last_days = self.get_game_days();
+        let delta = self.update();
+        (delta, self.day_rolled_over_since(last_days))
+    }
+
+    /**
+     * Number of complete game days elapsed, derived from `game_ticks`.
+     */
+    pub fn get_game_days(&self) -> u64 {
+        self.game_ticks / self.day_ticks
+    }
+
+    /**
+     * Returns true if one or more days have begun since `get_game_days()`
+     * returned `last_days`. Multi-day jumps (e.g. from `rest`) count too.
+     */
+    pub fn day_rolled_over_since(&self, last_days: u64) -> bool {
+        self.get_game_days() > last_days
+    }
+
+    /**
+     * Position within the current day, equivalent to the original `daynight`
+     * counter (0..24000).
+     */
+    pub fn daynight(&self) -> u64 {
+        self.game_ticks % self.day_ticks
+    }
+
+    /**
+     * Set the position within the current day from a raw `daynight` value,
+     * e.g. as stored in a save file. The elapsed day count is preserved.
+     */
+    pub fn set_daynight(&mut self, daynight: u64) {
+        self.game_ticks = self.get_game_days() * self.day_ticks + daynight % self.day_ticks;
+    }
+
+    /**
+     * Current game hour, 0..=23.
+     */
+    pub fn get_hour(&self) -> u32 {
+        (self.daynight() / self.hour_ticks()) as u32
+    }
+
+    /**
+     * Current game time as (day, hour, minute). Uses integer math only: an
+     * hour is 1000 ticks, which doesn't divide evenly into minutes, so
+     * float conversions drift.
+     */
+    pub fn get_game_wall_clock(&self) -> (u64, u32, u32) {
+        let minute = (self.daynight() % self.hour_ticks()) * 60 / self.hour_ticks();
+        (self.get_game_days(), self.get_hour(), minute as u32)
+    }
+
+    /**
+     * Set the game time from (day, hour, minute). Minutes round up to the
+     * first tick that `get_game_wall_clock` reports as that minute, so a set
+     * followed by a get returns the same values. Hour and minute wrap.
+     */
+    pub fn set_game_wall_clock(&mut self, day: u64, hour: u32, minute: u32) {
+        let minute_ticks = (minute as u64 % 60 * self.hour_ticks()).div_ceil(60);
+        let daynight = hour as u64 % 24 * self.hour_ticks() + minute_ticks;
+        self.game_ticks = day * self.day_ticks + daynight;
+    }
+
+    /**
+     * Light level for the current time of day, following fmain.c:
+     * `daynight / 40`, mirrored above 300 so it ramps 0..300..0 over a day.
+     * Scaled to the day length, so a shortened day still peaks at 300.
+     */
+    pub fn get_light_level(&self) -> u16 {
+        let level = self.daynight() * 600 / self.day_ticks;
+        if level >= 300 {
+            (600 - level) as u16
+        } else {
+            level as u16
+        }
+    }
+
+    /**
+     * Current phase of day: the latest phase whose dayperiod bucket
+     * (`daynight / 2000`) has started.
+     */
+    pub fn get_day_phase(&self) -> DayPhase {
+        self.phase_at_tick(self.game_ticks)
+    }
+
+    /**
+     * Phase of day at an absolute `game_ticks` value, for checking arbitrary
+     * times such as "only available at night". Depends only on the day
+     * length, not on the clock's current time.
+     */
+    pub fn phase_at_tick(&self, tick: u64) -> DayPhase {
+        let period = (tick % self.day_ticks) / self.period_ticks();
+        (0..=period)
+            .rev()
+            .find_map(phase_starting_at)
+            .unwrap_or(DayPhase::Midnight)
+    }
+
+    /**
+     * Fast-forward the game clock while resting or sleeping.
+     * Advances `game_ticks` one hour or dayperiod boundary at a time so that
+     * every phase transition crossed along the way is reported to `on_phase`,
+     * in order, just as if the ticks had elapsed normally. A phase starting
+     * on the same tick as an hour is reported before the hour callback, the
+     * same order as `poll_events`.
+     */
+    pub fn rest<F: FnMut(DayPhase)>(&mut self, ticks: u64, mut on_phase: F) {
+        let target = self.game_ticks + ticks;
+        let hour_ticks = self.hour_ticks();
+        let period_ticks = self.period_ticks();
+        while self.game_ticks < target {
+            let before = self.game_ticks;
+            let next_hour = (before / hour_ticks + 1) * hour_ticks;
+            let next_period = (before / period_ticks + 1) * period_ticks;
+            self.game_ticks = next_hour.min(next_period).min(target);
+            if self.game_ticks == next_period {
+                let period = (self.game_ticks % self.day_ticks) / period_ticks;
+                if let Some(phase) = phase_starting_at(period) {
+                    on_phase(phase);
+                }
+            }
+            self.notify_hours(before);
+        }
+    }
+
+    /**
+     * Register a callback fired with the new hour of day each time the game
+     * clock passes an hour boundary during `update` or `rest`. A jump across
+     * several hours fires once per hour, in order. Replaces any previous
+     * callback.
+     */
+    pub fn on_hour(&mut self, callback: HourCallback) {
+        self.hour_listener = HourListener(Some(callback));
+    }
+
+    /**
+     * Drain everything that happened to the clock since the last call, in
+     * the order it happened. Events are queued by `update` and `rest`; when
+     * several land on the same tick they are ordered day rollover, then
+     * phase, then hour. At most `MAX_PENDING_EVENTS` are kept between polls,
+     * so a clock nobody polls drops its oldest events instead of growing.
+     */
+    pub fn poll_events(&mut self) -> Vec<TimeEvent> {
+        self.pending_events.drain(..).collect()
+    }
+
+    // Fire the hour callback and queue time events for every hour boundary
+    // crossed since `before`. Day and phase boundaries always fall on hours.
+    fn notify_hours(&mut self, before: u64) {
+        let hour_ticks = self.hour_ticks();
+        let period_ticks = self.period_ticks();
+        for hours in before / hour_ticks + 1..=self.game_ticks / hour_ticks {
+            let tick = hours * hour_ticks;
+            if tick.is_multiple_of(self.day_ticks) {
+                self.queue_event(TimeEvent::DayRollover(tick / self.day_ticks));
+            }
+            if tick.is_multiple_of(period_ticks) {
+                let period = (tick % self.day_ticks) / period_ticks;
+                if let Some(phase) = phase_starting_at(period) {
+                    let event_id = phase_event_id(phase);
+                    self.queue_event(TimeEvent::Phase { phase, event_id });
+                }
+            }
+            let hour = (hours % HOURS_PER_DAY) as u32;
+            self.queue_event(TimeEvent::Hour(hour));
+            if let Some(callback) = self.hour_listener.0.as_mut() {
+                callback(hour);
+            }
+        }
+    }
+
+    // Queue an event for poll_events, dropping the oldest once the queue is full.
+    fn queue_event(&mut self, event: TimeEvent) {
+        if self.pending_events.len() >= MAX_PENDING_EVENTS {
+            self.pending_events.pop_front();
+        }
+        self.pending_events.push_back(event);
+    }
+
+    /**
+     * Jump forward to the start of the next day phase, reporting the phase
+     * transition to `on_phase` as `rest` does.
+     */
+    pub fn advance_to_next_phase<F: FnMut(DayPhase)>(&mut self, on_phase: F) {
+        let next = (self.game_ticks / self.period_ticks() + 1..)
+            .find(|period| phase_starting_at(period % PERIODS_PER_DAY).is_some())
+            .unwrap();
+        self.rest(next * self.period_ticks() - self.game_ticks, on_phase);
+    }
+
+    /**
+     * Jump back to the start of the current day phase, or of the previous
+     * phase if already exactly at a phase start. Never goes below tick zero.
+     * No transition is reported.
+     */
+    pub fn rewind_to_phase_start(&mut self) {
+        if self.game_ticks == 0 {
+            return;
+        }
+        let start = (0..=(self.game_ticks - 1) / self.period_ticks())
+            .rev()
+            .find(|period| phase_starting_at(period % PERIODS_PER_DAY).is_some())
+            .unwrap_or(0);
+        self.game_ticks = start * self.period_ticks();
+    }
+
     /**
      * Reset the game ticks to zero (e.g., on player death or new game).
      */
     pub fn reset_game_ticks(&mut self) {
         self.game_ticks = 0;
-        self.ticker.reset();
+        self.ticker.reset(self.time_source.now());
     }
 
     /**
@@ -162,7 +566,7 @@ impl GameClock {
      * Resume the game clock.
      */
     pub fn resume(&mut self) {
-        self.ticker.reset();
+        self.ticker.reset(self.time_source.now());
         self.paused = false;
         println!(
             "Game clock resumed at {} total ticks, {} game ticks",
@@ -170,3 +574,428 @@ impl GameClock {
         );
     }
 }
+
+/**
+ * Table of (start hour, entry) pairs, such as an NPC's daily routine. Each
+ * entry runs from its start hour until the next entry begins; the last entry
+ * carries on past midnight until the first one starts.
+ */
+#[derive(Debug, Clone)]
+pub struct Schedule<T> {
+    entries: Vec<(u32, T)>, // sorted by start hour
+}
+
+impl<T> Schedule<T> {
+    pub fn new(mut entries: Vec<(u32, T)>) -> Schedule<T> {
+        entries.sort_by_key(|(hour, _)| *hour);
+        Schedule { entries }
+    }
+
+    /**
+     * Entry active at `hour` (0..=23), or None if the schedule is empty.
+     */
+    pub fn at_hour(&self, hour: u32) -> Option<&T> {
+        let started = self.entries.partition_point(|(start, _)| *start <= hour);
+        let index = if started == 0 {
+            // before the first start hour: still in the previous day's last entry
+            self.entries.len().checked_sub(1)?
+        } else {
+            started - 1
+        };
+        Some(&self.entries[index].1)
+    }
+
+    /**
+     * Entry active at the clock's current game hour.
+     */
+    pub fn current<'a>(&'a self, clock: &GameClock) -> Option<&'a T> {
+        self.at_hour(clock.get_hour())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rest_fires_crossed_phases_in_order() {
+        let mut clock = GameClock::new();
+        clock.game_ticks = 7_000; // late night, period 3
+
+        let mut phases = Vec::new();
+        clock.rest(6_000, |phase| phases.push(phase));
+
+        assert_eq!(clock.game_ticks, 13_000);
+        assert_eq!(phases, vec![DayPhase::Morning, DayPhase::Midday]);
+    }
+
+    #[test]
+    fn test_day_rollover_detected() {
+        let mut clock = GameClock::new();
+        clock.game_ticks = DAY_TICKS - 10;
+        let last_days = clock.get_game_days();
+        assert!(!clock.day_rolled_over_since(last_days));
+
+        clock.rest(5, |_| {});
+        assert!(!clock.day_rolled_over_since(last_days));
+
+        clock.rest(5, |_| {});
+        assert!(clock.day_rolled_over_since(last_days));
+        assert_eq!(clock.get_game_days(), 1);
+
+        // multi-day jump
+        let last_days = clock.get_game_days();
+        clock.rest(3 * DAY_TICKS, |_| {});
+        assert!(clock.day_rolled_over_since(last_days));
+        assert_eq!(clock.get_game_days(), 4);
+    }
+
+    #[test]
+    fn test_update_reports_no_rollover_mid_day() {
+        let mut clock = GameClock::new();
+        clock.game_ticks = 100;
+        let (_, rolled_over) = clock.update_with_day_rollover();
+        assert!(!rolled_over);
+    }
+
+    #[test]
+    fn test_set_daynight_matches_original_formulas() {
+        let mut clock = GameClock::new();
+        clock.game_ticks = 2 * DAY_TICKS + 500;
+
+        // (daynight, lightlevel, phase) per fmain.c
+        let cases = [
+            (0, 0, DayPhase::Midnight),
+            (7_999, 199, DayPhase::Midnight),
+            (8_000, 200, DayPhase::Morning),
+            (12_000, 300, DayPhase::Midday),
+            (17_999, 151, DayPhase::Midday),
+            (18_000, 150, DayPhase::Evening),
+            (23_999, 1, DayPhase::Evening),
+        ];
+        for (daynight, light, phase) in cases {
+            clock.set_daynight(daynight);
+            assert_eq!(clock.daynight(), daynight);
+            assert_eq!(clock.get_game_days(), 2);
+            assert_eq!(clock.get_light_level(), light, "daynight {daynight}");
+            assert_eq!(clock.get_day_phase(), phase, "daynight {daynight}");
+        }
+
+        // raw values past a full day wrap like the original counter
+        clock.set_daynight(DAY_TICKS + 40);
+        assert_eq!(clock.daynight(), 40);
+    }
+
+    #[test]
+    fn test_schedule_wraps_past_midnight() {
+        let schedule = Schedule::new(vec![(22, "sleep"), (6, "work"), (18, "tavern")]);
+        let mut clock = GameClock::new();
+
+        for (hour, expected) in [
+            (6, "work"),
+            (12, "work"),
+            (18, "tavern"),
+            (21, "tavern"),
+            (22, "sleep"),
+            (23, "sleep"),
+            (0, "sleep"),
+            (5, "sleep"),
+        ] {
+            clock.set_daynight(hour * HOUR_TICKS + 500);
+            assert_eq!(clock.get_hour(), hour as u32);
+            assert_eq!(schedule.current(&clock), Some(&expected), "hour {hour}");
+        }
+
+        let empty: Schedule<&str> = Schedule::new(Vec::new());
+        assert_eq!(empty.current(&clock), None);
+    }
+
+    #[test]
+    fn test_real_elapsed_runs_while_paused() {
+        // start, pause's update, the first real_elapsed, then 50ms later
+        let mut clock = scripted_clock(&[0, 0, 0, 50]);
+        clock.game_ticks = 500;
+        clock.pause();
+
+        assert_eq!(clock.real_elapsed(), Duration::ZERO);
+        clock.update();
+
+        assert_eq!(clock.real_elapsed(), Duration::from_millis(50));
+        assert_eq!(clock.game_ticks, 500);
+    }
+
+    // Clock whose time source steps through `offsets` from a fixed start,
+    // repeating the last one once the script runs out.
+    fn scripted_clock(offsets: &[u64]) -> GameClock {
+        let start = Instant::now();
+        let instants: Vec<Instant> = offsets
+            .iter()
+            .map(|&ms| start + Duration::from_millis(ms))
+            .collect();
+        let mut next = 0;
+        GameClock::new_with_clock(Box::new(move || {
+            let now = instants[next.min(instants.len() - 1)];
+            next += 1;
+            now
+        }))
+    }
+
+    #[test]
+    fn test_scripted_time_accumulates_exact_ticks() {
+        let mut clock = scripted_clock(&[0, 100, 150, 1000]);
+
+        // 100ms is 2 ticks, carrying the remainder into the next update
+        assert_eq!(clock.update(), 2);
+        assert_eq!(clock.game_ticks, 2);
+        clock.update();
+        assert_eq!(clock.game_ticks, 4);
+        clock.update();
+        assert_eq!(clock.game_ticks, 29);
+        assert_eq!(clock.mono_ticks, 29);
+        assert_eq!(clock.real_elapsed(), Duration::from_secs(1));
+
+        // no time passes once the script is exhausted
+        clock.update();
+        assert_eq!(clock.game_ticks, 29);
+    }
+
+    #[test]
+    fn test_update_clamps_long_stall() {
+        // an hour-long suspend, a normal 100ms frame, then another hour
+        let mut clock = scripted_clock(&[0, 3_600_000, 3_600_100, 7_200_100]);
+        clock.game_ticks = 500;
+
+        clock.update();
+        assert_eq!(clock.game_ticks, 500 + DEFAULT_MAX_DELTA_TICKS);
+
+        // the excess was discarded, not carried into the next update
+        clock.update();
+        assert_eq!(clock.game_ticks, 500 + DEFAULT_MAX_DELTA_TICKS + 3);
+
+        clock.set_max_delta_ticks_per_update(10);
+        let before = clock.game_ticks;
+        clock.update();
+        assert_eq!(clock.game_ticks, before + 10);
+    }
+
+    #[test]
+    fn test_elapsed_since_stamp() {
+        let mut clock = GameClock::new();
+        clock.game_ticks = 5_000;
+        let stamp = clock.game_ticks;
+
+        // one day, three hours and 30 minutes later
+        clock.game_ticks += DAY_TICKS + 3 * HOUR_TICKS + HOUR_TICKS / 2;
+        assert_eq!(clock.elapsed_since(stamp), (1, 3, 30));
+        assert_eq!(clock.elapsed_since(clock.game_ticks), (0, 0, 0));
+
+        // a stamp from the future reads as no time at all
+        assert_eq!(clock.elapsed_since(clock.game_ticks + 1), (0, 0, 0));
+
+        let later = Instant::now() + Duration::from_secs(60);
+        assert_eq!(clock.real_since(later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_wall_clock_round_trip_every_minute() {
+        let mut clock = GameClock::new();
+        for hour in 0..24 {
+            for minute in 0..60 {
+                clock.set_game_wall_clock(3, hour, minute);
+                assert_eq!(clock.get_game_wall_clock(), (3, hour, minute));
+            }
+        }
+        clock.game_ticks = 23_500;
+        assert_eq!(clock.get_game_wall_clock(), (0, 23, 30));
+    }
+
+    #[test]
+    fn test_original_save_round_trip() {
+        let clock = GameClock::from_original_save(12, 18_500);
+        assert_eq!(clock.get_game_days(), 12);
+        assert_eq!(clock.daynight(), 18_500);
+        assert_eq!(clock.get_day_phase(), DayPhase::Evening);
+        assert_eq!(clock.to_original_save(), (12, 18_500));
+
+        // out of range daynight wraps into the next day
+        let clock = GameClock::from_original_save(3, 24_100);
+        assert_eq!(clock.to_original_save(), (4, 100));
+    }
+
+    #[test]
+    fn test_phase_navigation() {
+        let mut clock = GameClock::new();
+        clock.game_ticks = DAY_TICKS + 13_000; // midday of day 1
+
+        let mut fired = Vec::new();
+        clock.advance_to_next_phase(|phase| fired.push(phase));
+        assert_eq!(clock.game_ticks, DAY_TICKS + 18_000);
+        assert_eq!(fired, vec![DayPhase::Evening]);
+
+        // evening runs to midnight of the following day
+        clock.advance_to_next_phase(|phase| fired.push(phase));
+        assert_eq!(clock.game_ticks, 2 * DAY_TICKS);
+        assert_eq!(fired.last(), Some(&DayPhase::Midnight));
+
+        // at a phase start, rewinding goes back to the previous one
+        clock.rewind_to_phase_start();
+        assert_eq!(clock.game_ticks, DAY_TICKS + 18_000);
+        clock.game_ticks += 500;
+        clock.rewind_to_phase_start();
+        assert_eq!(clock.game_ticks, DAY_TICKS + 18_000);
+
+        clock.game_ticks = 0;
+        clock.rewind_to_phase_start();
+        assert_eq!(clock.game_ticks, 0);
+    }
+
+    #[test]
+    fn test_on_hour_fires_for_each_crossed_hour() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let hours: Rc<RefCell<Vec<u32>>> = Rc::new(RefCell::new(Vec::new()));
+        let mut clock = GameClock::new();
+        let sink = hours.clone();
+        clock.on_hour(Box::new(move |hour| sink.borrow_mut().push(hour)));
+
+        clock.game_ticks = 10 * HOUR_TICKS + 500;
+        clock.rest(3 * HOUR_TICKS, |_| {});
+        assert_eq!(*hours.borrow(), vec![11, 12, 13]);
+
+        // no boundary crossed
+        clock.rest(100, |_| {});
+        assert_eq!(hours.borrow().len(), 3);
+
+        // wraps past midnight
+        clock.game_ticks = 23 * HOUR_TICKS + 900;
+        clock.rest(200, |_| {});
+        assert_eq!(hours.borrow().last(), Some(&0));
+    }
+
+    #[test]
+    fn test_short_day_length_scales_days_and_phases() {
+        let mut clock = GameClock::new();
+        clock.set_day_length_ticks(2_400);
+        assert_eq!(clock.day_length_ticks(), 2_400);
+
+        clock.game_ticks = 3 * 2_400 + 100;
+        assert_eq!(clock.get_game_days(), 3);
+        assert_eq!(clock.get_hour(), 1);
+
+        // phases start at dayperiods 4, 6 and 9 of 200 ticks each
+        clock.set_daynight(799);
+        assert_eq!(clock.get_day_phase(), DayPhase::Midnight);
+        clock.set_daynight(800);
+        assert_eq!(clock.get_day_phase(), DayPhase::Morning);
+        clock.set_daynight(1_200);
+        assert_eq!(clock.get_day_phase(), DayPhase::Midday);
+        clock.set_daynight(1_800);
+        assert_eq!(clock.get_day_phase(), DayPhase::Evening);
+        assert_eq!(clock.get_light_level(), 150);
+
+        let mut phases = Vec::new();
+        clock.rest(600, |phase| phases.push(phase));
+        assert_eq!(phases, vec![DayPhase::Midnight]);
+        assert_eq!(clock.get_game_days(), 4);
+        assert_eq!(clock.to_original_save(), (4, 0));
+
+        clock.set_day_length_ticks(0);
+        assert_eq!(clock.day_length_ticks(), 24);
+        assert_eq!(clock.get_game_days(), 4);
+    }
+
+    #[test]
+    fn test_rest_wraps_to_midnight() {
+        let mut clock = GameClock::new();
+        clock.game_ticks = DAY_TICKS - 1;
+
+        let mut phases = Vec::new();
+        clock.rest(1, |phase| phases.push(phase));
+
+        assert_eq!(phases, vec![DayPhase::Midnight]);
+    }
+
+    #[test]
+    fn test_poll_events_orders_simultaneous_boundaries() {
+        let mut clock = GameClock::new();
+        clock.game_ticks = 8 * HOUR_TICKS - 50;
+        clock.rest(HOUR_TICKS + 100, |_| {});
+
+        // the Morning phase and hour 8 start on the same tick
+        let morning = TimeEvent::Phase {
+            phase: DayPhase::Morning,
+            event_id: 29,
+        };
+        assert_eq!(
+            clock.poll_events(),
+            vec![morning, TimeEvent::Hour(8), TimeEvent::Hour(9)]
+        );
+        assert!(clock.poll_events().is_empty());
+
+        clock.game_ticks = DAY_TICKS - 50;
+        clock.rest(100, |_| {});
+        let midnight = TimeEvent::Phase {
+            phase: DayPhase::Midnight,
+            event_id: 28,
+        };
+        assert_eq!(
+            clock.poll_events(),
+            vec![TimeEvent::DayRollover(1), midnight, TimeEvent::Hour(0)]
+        );
+    }
+
+    #[test]
+    fn test_rest_reports_phase_before_hour() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let order: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+        let mut clock = GameClock::new();
+        let sink = order.clone();
+        clock.on_hour(Box::new(move |hour| {
+            sink.borrow_mut().push(format!("hour {hour}"))
+        }));
+
+        clock.game_ticks = 7 * HOUR_TICKS + 500;
+        clock.rest(2 * HOUR_TICKS, |phase| {
+            order.borrow_mut().push(format!("{phase:?}"))
+        });
+        assert_eq!(*order.borrow(), vec!["Morning", "hour 8", "hour 9"]);
+    }
+
+    #[test]
+    fn test_unpolled_events_are_capped() {
+        let mut clock = GameClock::new();
+        clock.rest(30 * DAY_TICKS, |_| {});
+
+        let events = clock.poll_events();
+        assert_eq!(events.len(), MAX_PENDING_EVENTS);
+        // the newest events survive
+        assert_eq!(events.last(), Some(&TimeEvent::Hour(0)));
+    }
+
+    #[test]
+    fn test_phase_at_tick_boundaries() {
+        let mut clock = GameClock::new();
+        clock.game_ticks = 5 * DAY_TICKS + 10_000;
+        let phases = [
+            (0, DayPhase::Midnight),
+            (7_999, DayPhase::Midnight),
+            (8_000, DayPhase::Morning),
+            (11_999, DayPhase::Morning),
+            (12_000, DayPhase::Midday),
+            (17_999, DayPhase::Midday),
+            (18_000, DayPhase::Evening),
+            (23_999, DayPhase::Evening),
+            (DAY_TICKS, DayPhase::Midnight),
+            (3 * DAY_TICKS + 8_000, DayPhase::Morning),
+        ];
+        for (tick, phase) in phases {
+            assert_eq!(clock.phase_at_tick(tick), phase, "tick {}", tick);
+        }
+        // the clock's own time doesn't matter
+        assert_eq!(clock.get_day_phase(), DayPhase::Morning);
+        assert_eq!(clock.phase_at_tick(19_000), DayPhase::Evening);
+    }
+}
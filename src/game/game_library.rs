@@ -21,6 +21,10 @@ use std::{collections::HashMap, error::Error, fs, path::Path};
  * by path and loaded from files. All assets are loaded at startup.
  */
 
+/// Oldest library schema version this code understands. Bump it along with
+/// the `version` in faery.toml when the code starts relying on new data.
+pub const MIN_SUPPORTED_VERSION: u32 = 1;
+
 #[derive(Deserialize, Debug)]
 pub struct CopyProtectQuestion {
     pub question: String,
@@ -181,8 +185,49 @@ pub struct NarrConfig {
     pub inside_msg: Vec<String>,
 }
 
+/// The kinds of named asset held by the library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    Palette,
+    Placard,
+    Font,
+    Image,
+    Cursor,
+}
+
+/// Stable handle to a library asset, resolved once after loading. Ids stay
+/// valid for the lifetime of the library they came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AssetId {
+    kind: AssetKind,
+    index: usize,
+}
+
+impl AssetId {
+    pub fn kind(&self) -> AssetKind {
+        self.kind
+    }
+}
+
+/// Borrowed reference to an asset of any kind.
+#[derive(Debug, Clone, Copy)]
+pub enum AssetRef<'a> {
+    Palette(&'a Palette),
+    Placard(&'a Placard),
+    Font(&'a FontAsset),
+    Image(&'a ImageAsset),
+    Cursor(&'a CursorAsset),
+}
+
 #[derive(Deserialize, Debug)]
 pub struct GameLibrary {
+    /// Schema version of the data file; files without one count as version 0.
+    #[serde(default)]
+    pub version: u32,
+    /// Optional checksum recorded by whatever generated the data file, for
+    /// telling builds of the same version apart. Not verified on load.
+    #[serde(default)]
+    pub checksum: Option<String>,
     palettes: HashMap<String, Palette>,
     placards: HashMap<String, Placard>,
     fonts: HashMap<String, FontAsset>,
@@ -208,9 +253,59 @@ pub struct GameLibrary {
     pub narr: NarrConfig,
     #[serde(default)]
     pub objects: Vec<ObjectConfig>,
+
+    // sorted asset names per kind; an AssetId indexes into these
+    #[serde(skip)]
+    asset_names: HashMap<AssetKind, Vec<String>>,
 }
 
 impl GameLibrary {
+    // Assign ids to every named asset. Names are sorted so ids are stable
+    // for a given library file.
+    fn index_assets(&mut self) {
+        fn sorted_names<T>(assets: &HashMap<String, T>) -> Vec<String> {
+            let mut names: Vec<String> = assets.keys().cloned().collect();
+            names.sort();
+            names
+        }
+
+        self.asset_names = HashMap::from([
+            (AssetKind::Palette, sorted_names(&self.palettes)),
+            (AssetKind::Placard, sorted_names(&self.placards)),
+            (AssetKind::Font, sorted_names(&self.fonts)),
+            (AssetKind::Image, sorted_names(&self.images)),
+            (AssetKind::Cursor, sorted_names(&self.cursors)),
+        ]);
+    }
+
+    // generic asset lookup
+    pub fn asset_id(&self, kind: AssetKind, name: &str) -> Option<AssetId> {
+        let names = self.asset_names.get(&kind)?;
+        let index = names.binary_search_by(|n| n.as_str().cmp(name)).ok()?;
+        Some(AssetId { kind, index })
+    }
+
+    pub fn asset_name(&self, id: AssetId) -> Option<&str> {
+        self.asset_names
+            .get(&id.kind)?
+            .get(id.index)
+            .map(|name| name.as_str())
+    }
+
+    pub fn get(&self, kind: AssetKind, name: &str) -> Option<AssetRef<'_>> {
+        match kind {
+            AssetKind::Palette => self.palettes.get(name).map(AssetRef::Palette),
+            AssetKind::Placard => self.placards.get(name).map(AssetRef::Placard),
+            AssetKind::Font => self.fonts.get(name).map(AssetRef::Font),
+            AssetKind::Image => self.images.get(name).map(AssetRef::Image),
+            AssetKind::Cursor => self.cursors.get(name).map(AssetRef::Cursor),
+        }
+    }
+
+    pub fn resolve(&self, id: AssetId) -> Option<AssetRef<'_>> {
+        self.get(id.kind, self.asset_name(id)?)
+    }
+
     // images
     pub fn get_image_count(&self) -> usize {
         self.images.len()
@@ -262,6 +357,18 @@ impl GameLibrary {
         self.placards.get(name)
     }
 
+    // placard names in sorted (id) order, for tooling that browses every placard
+    pub fn placard_names(&self) -> impl Iterator<Item = &str> {
+        let mut names: Vec<&str> = self.placards.keys().map(|name| name.as_str()).collect();
+        names.sort_unstable();
+        names.into_iter()
+    }
+
+    // placards in the same order as placard_names()
+    pub fn placards(&self) -> impl Iterator<Item = &Placard> {
+        self.placard_names().map(|name| &self.placards[name])
+    }
+
     // fonts
     pub fn get_font_count(&self) -> usize {
         self.fonts.len()
@@ -307,6 +414,22 @@ impl GameLibrary {
             .find(|r| r.id == region_num)
     }
 
+    /// Describes the version mismatch if the library is older than
+    /// `MIN_SUPPORTED_VERSION`, or `None` if it is supported.
+    pub fn version_warning(&self) -> Option<String> {
+        if self.version >= MIN_SUPPORTED_VERSION {
+            return None;
+        }
+        let checksum = match &self.checksum {
+            Some(checksum) => format!(" (checksum {})", checksum),
+            None => String::new(),
+        };
+        Some(format!(
+            "game library version {}{} is older than the minimum supported version {}",
+            self.version, checksum, MIN_SUPPORTED_VERSION
+        ))
+    }
+
     // locations
     pub fn find_location(&self, name: &str) -> Option<&LocationConfig> {
         self.locations.iter().find(|l| l.name == name)
@@ -332,18 +455,79 @@ impl GameLibrary {
     }
 }
 
-pub fn load_game_library(lib_path: &Path) -> Result<GameLibrary, Box<dyn Error>> {
+/// Why `load_game_library` failed.
+#[derive(Debug)]
+pub enum LibraryError {
+    Io(std::io::Error),   // the library file couldn't be read
+    Parse(String),        // bad TOML or schema mismatch, with line and column
+    MissingAsset(String), // a font or image the library names failed to load
+    OutdatedData(String), // the library version is below MIN_SUPPORTED_VERSION
+}
+
+impl std::fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibraryError::Io(e) => write!(f, "Failed to read game library: {}", e),
+            LibraryError::Parse(msg) => write!(f, "Failed to parse game library: {}", msg),
+            LibraryError::MissingAsset(msg) => write!(f, "Failed to load asset: {}", msg),
+            LibraryError::OutdatedData(msg) => write!(f, "Outdated data file: {}", msg),
+        }
+    }
+}
+
+impl Error for LibraryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LibraryError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LibraryError {
+    fn from(e: std::io::Error) -> Self {
+        LibraryError::Io(e)
+    }
+}
+
+/// Load the game library, warning on stderr if its version is older than
+/// `MIN_SUPPORTED_VERSION`.
+pub fn load_game_library(lib_path: &Path) -> Result<GameLibrary, LibraryError> {
+    load_game_library_opts(lib_path, false)
+}
+
+/// Load the game library. An outdated version is an `OutdatedData` error
+/// when `strict_version` is set, otherwise only a warning.
+pub fn load_game_library_opts(
+    lib_path: &Path,
+    strict_version: bool,
+) -> Result<GameLibrary, LibraryError> {
     let config = fs::read_to_string(lib_path)?;
-    let mut game_lib = toml::from_str::<GameLibrary>(&config)?;
+    // toml's message includes the line, column and offending snippet
+    let mut game_lib =
+        toml::from_str::<GameLibrary>(&config).map_err(|e| LibraryError::Parse(e.to_string()))?;
+
+    // check before loading assets, since old data may also name missing files
+    if let Some(warning) = game_lib.version_warning() {
+        if strict_version {
+            return Err(LibraryError::OutdatedData(warning));
+        }
+        eprintln!("warning: {}", warning);
+    }
 
     // preload all file based assets
-    for font_asset in game_lib.fonts.values_mut() {
-        font_asset.load()?;
+    for (name, font_asset) in game_lib.fonts.iter_mut() {
+        font_asset
+            .load()
+            .map_err(|e| LibraryError::MissingAsset(format!("font {}: {}", name, e)))?;
     }
 
-    for image_asset in game_lib.images.values_mut() {
-        image_asset.image = Some(IffImage::load_from_file(Path::new(&image_asset.path))?);
+    for (name, image_asset) in game_lib.images.iter_mut() {
+        let image = IffImage::load_from_file(Path::new(&image_asset.path))
+            .map_err(|e| LibraryError::MissingAsset(format!("image {}: {}", name, e)))?;
+        image_asset.image = Some(image);
     }
+    game_lib.index_assets();
 
     Ok(game_lib)
 }
@@ -355,8 +539,62 @@ mod tests {
     fn load_library() -> GameLibrary {
         let config =
             fs::read_to_string("faery.toml").expect("faery.toml should exist in the project root");
-        toml::from_str::<GameLibrary>(&config)
-            .expect("faery.toml should deserialize into GameLibrary without errors")
+        let mut lib = toml::from_str::<GameLibrary>(&config)
+            .expect("faery.toml should deserialize into GameLibrary without errors");
+        lib.index_assets();
+        lib
+    }
+
+    #[test]
+    fn test_load_game_library_errors() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let missing = load_game_library(&dir.path().join("nope.toml")).unwrap_err();
+        assert!(matches!(missing, LibraryError::Io(_)), "{:?}", missing);
+
+        let malformed = dir.path().join("bad.toml");
+        fs::write(&malformed, "[palettes]\nbroken = [1, 2\n").unwrap();
+        match load_game_library(&malformed).unwrap_err() {
+            LibraryError::Parse(msg) => assert!(msg.contains("line"), "{}", msg),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+
+        let missing_image = dir.path().join("image.toml");
+        let config = fs::read_to_string("faery.toml").unwrap().replacen(
+            "file = \"game/page0\"",
+            "file = \"game/no_such_page\"",
+            1,
+        );
+        fs::write(&missing_image, config).unwrap();
+        match load_game_library(&missing_image).unwrap_err() {
+            LibraryError::MissingAsset(msg) => assert!(msg.contains("page0"), "{}", msg),
+            other => panic!("expected a missing asset error, got {:?}", other),
+        }
+
+        assert!(load_game_library(Path::new("faery.toml")).is_ok());
+    }
+
+    #[test]
+    fn test_library_version_check() {
+        let current = load_game_library_opts(Path::new("faery.toml"), true).unwrap();
+        assert!(current.version >= MIN_SUPPORTED_VERSION);
+        assert_eq!(current.version_warning(), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old.toml");
+        let config = fs::read_to_string("faery.toml").unwrap();
+        let line = format!("version = {}", current.version);
+        assert!(config.contains(&line));
+        fs::write(&old, config.replacen(&line, "version = 0", 1)).unwrap();
+
+        let lenient = load_game_library_opts(&old, false).unwrap();
+        let warning = lenient.version_warning().unwrap();
+        assert!(warning.contains("version 0"), "{}", warning);
+
+        match load_game_library_opts(&old, true).unwrap_err() {
+            LibraryError::OutdatedData(msg) => assert_eq!(msg, warning),
+            other => panic!("expected an outdated data error, got {:?}", other),
+        }
     }
 
     /// Ensure faery.toml can be deserialized into GameLibrary without errors.
@@ -366,6 +604,117 @@ mod tests {
         load_library();
     }
 
+    #[test]
+    fn test_named_palettes_from_toml_and_json() {
+        let toml_lib = toml::from_str::<GameLibrary>(
+            r#"
+            copy_protect_junk = []
+            [placards]
+            [fonts]
+            [images]
+            [cursors]
+            [palettes.scene]
+            colors = [0x000, 0xFFF, 0xE00]
+            [palettes.ui]
+            colors = [0x123, 0x456]
+            "#,
+        )
+        .unwrap();
+        let json_lib = serde_json::from_str::<GameLibrary>(
+            r#"{
+                "palettes": {
+                    "scene": { "colors": [0, 4095, 3584] },
+                    "ui": { "colors": [291, 1110] }
+                },
+                "placards": {}, "fonts": {}, "images": {}, "cursors": {},
+                "copy_protect_junk": []
+            }"#,
+        )
+        .unwrap();
+
+        for lib in [toml_lib, json_lib] {
+            assert_eq!(lib.get_palette_count(), 2);
+            let scene: Vec<u16> = lib
+                .find_palette("scene")
+                .unwrap()
+                .colors
+                .iter()
+                .map(|c| c.color)
+                .collect();
+            assert_eq!(scene, vec![0x000, 0xFFF, 0xE00]);
+            let ui: Vec<u16> = lib
+                .find_palette("ui")
+                .unwrap()
+                .colors
+                .iter()
+                .map(|c| c.color)
+                .collect();
+            assert_eq!(ui, vec![0x123, 0x456]);
+            assert!(lib.find_palette("missing").is_none());
+        }
+    }
+
+    #[test]
+    fn test_placard_iterators() {
+        let lib = load_library();
+        let names: Vec<&str> = lib.placard_names().collect();
+        assert_eq!(names.len(), lib.get_placard_count());
+        assert!(names.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let placards: Vec<&Placard> = lib.placards().collect();
+        assert_eq!(placards.len(), names.len());
+        for (index, (name, placard)) in names.iter().zip(placards).enumerate() {
+            let id = AssetId {
+                kind: AssetKind::Placard,
+                index,
+            };
+            assert_eq!(lib.asset_name(id), Some(*name));
+            assert!(std::ptr::eq(lib.find_placard(name).unwrap(), placard));
+        }
+    }
+
+    #[test]
+    fn test_placard_iterators_without_index() {
+        let config =
+            fs::read_to_string("faery.toml").expect("faery.toml should exist in the project root");
+        let lib = toml::from_str::<GameLibrary>(&config)
+            .expect("faery.toml should deserialize into GameLibrary without errors");
+        assert_eq!(lib.placard_names().count(), lib.get_placard_count());
+        assert_eq!(lib.placards().count(), lib.get_placard_count());
+    }
+
+    #[test]
+    fn test_asset_lookup_by_id_and_name() {
+        let lib = load_library();
+        let cases = [
+            (AssetKind::Palette, "introcolors"),
+            (AssetKind::Placard, "titletext"),
+            (AssetKind::Font, "topaz"),
+            (AssetKind::Image, "hiscreen"),
+            (AssetKind::Cursor, "bow"),
+        ];
+        for (kind, name) in cases {
+            let id = lib.asset_id(kind, name).expect(name);
+            assert_eq!(id.kind(), kind);
+            assert_eq!(lib.asset_name(id), Some(name));
+
+            let by_name = lib.get(kind, name).expect(name);
+            let by_id = lib.resolve(id).expect(name);
+            let same = match (by_name, by_id) {
+                (AssetRef::Palette(a), AssetRef::Palette(b)) => std::ptr::eq(a, b),
+                (AssetRef::Placard(a), AssetRef::Placard(b)) => std::ptr::eq(a, b),
+                (AssetRef::Font(a), AssetRef::Font(b)) => std::ptr::eq(a, b),
+                (AssetRef::Image(a), AssetRef::Image(b)) => std::ptr::eq(a, b),
+                (AssetRef::Cursor(a), AssetRef::Cursor(b)) => std::ptr::eq(a, b),
+                _ => false,
+            };
+            assert!(same, "{name} resolved to a different asset by id");
+        }
+
+        assert!(lib.asset_id(AssetKind::Image, "no_such_image").is_none());
+        assert!(lib.get(AssetKind::Cursor, "titletext").is_none());
+    }
+
     #[test]
     fn test_objects_for_region_filters_correctly() {
         let lib = load_library();
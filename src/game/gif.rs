@@ -0,0 +1,374 @@
+//! Minimal indexed GIF89a writer, for sharing sprites and debug captures.
+//!
+//! Frames are written with the palette as the global color table and LZW
+//! compressed pixel indices. Animated files loop forever.
+
+use crate::game::bitmap::BitMap;
+use crate::game::colors::Palette;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+const GIF_MAX_CODE_SIZE: u8 = 12;
+const GIF_MAX_CODES: u16 = 1 << GIF_MAX_CODE_SIZE;
+const GIF_MAX_SUB_BLOCK: usize = 255;
+
+// Disposal method 2: restore the frame area to the background before the
+// next frame, so transparent pixels don't show the previous frame.
+const GIF_DISPOSE_BACKGROUND: u8 = 2 << 2;
+
+/// Write frames of equal size as an animated GIF. `delays` holds each
+/// frame's display time in hundredths of a second; missing entries are 0.
+/// `transparent_index`, if set, is marked as the GIF transparent color.
+pub fn save_animated_gif(
+    frames: &[BitMap],
+    palette: &Palette,
+    delays: &[u16],
+    transparent_index: Option<usize>,
+    path: &Path,
+) -> Result<(), String> {
+    let data = encode_gif(frames, palette, delays, transparent_index)?;
+    std::fs::write(path, data).map_err(|e| format!("Failed to write GIF {:?}: {}", path, e))
+}
+
+/// Encode frames as a GIF file in memory. A single frame produces a still
+/// image; more than one adds a looping animation extension.
+pub fn encode_gif(
+    frames: &[BitMap],
+    palette: &Palette,
+    delays: &[u16],
+    transparent_index: Option<usize>,
+) -> Result<Vec<u8>, String> {
+    let first = frames.first().ok_or("GIF needs at least one frame")?;
+    let (width, height) = first.get_size();
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(format!("{}x{} is too large for a GIF", width, height));
+    }
+    if let Some(index) = frames.iter().position(|f| f.get_size() != (width, height)) {
+        return Err(format!(
+            "GIF frame {} is {:?}, expected {:?}",
+            index,
+            frames[index].get_size(),
+            (width, height)
+        ));
+    }
+
+    if let Some(index) = frames.iter().position(|f| f.depth > 8) {
+        return Err(format!(
+            "GIF frame {} has {} bitplanes, at most 8 are supported",
+            index, frames[index].depth
+        ));
+    }
+
+    // color table must be a power of two with at least 2 entries
+    let depth = frames.iter().map(|f| f.depth).max().unwrap_or(1).max(1);
+    let table_size = 1usize << depth;
+    if let Some(index) = transparent_index {
+        if index >= table_size {
+            return Err(format!(
+                "Transparent index {} is outside the {}-color table",
+                index, table_size
+            ));
+        }
+    }
+
+    let mut out: Vec<u8> = Vec::new();
+    out.extend_from_slice(b"GIF89a");
+
+    // logical screen descriptor with a global color table
+    out.extend_from_slice(&(width as u16).to_le_bytes());
+    out.extend_from_slice(&(height as u16).to_le_bytes());
+    out.push(0x80 | ((depth as u8 - 1) << 4) | (depth as u8 - 1));
+    out.push(0); // background color index
+    out.push(0); // pixel aspect ratio
+    for index in 0..table_size {
+        match palette.get_color(index) {
+            Some(c) => out.extend_from_slice(&[c.r(), c.g(), c.b()]),
+            None => out.extend_from_slice(&[0, 0, 0]),
+        }
+    }
+
+    if frames.len() > 1 {
+        // NETSCAPE2.0 application extension, loop count 0 = forever
+        out.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+        out.extend_from_slice(b"NETSCAPE2.0");
+        out.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+    }
+
+    let min_code_size = (depth as u8).max(2);
+    for (index, frame) in frames.iter().enumerate() {
+        // graphic control extension
+        let delay = delays.get(index).copied().unwrap_or(0);
+        let transparent_flag = if transparent_index.is_some() { 1 } else { 0 };
+        out.extend_from_slice(&[0x21, 0xF9, 0x04]);
+        out.push(GIF_DISPOSE_BACKGROUND | transparent_flag);
+        out.extend_from_slice(&delay.to_le_bytes());
+        out.push(transparent_index.unwrap_or(0) as u8);
+        out.push(0);
+
+        // image descriptor covering the whole screen, no local color table
+        out.push(0x2C);
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&(width as u16).to_le_bytes());
+        out.extend_from_slice(&(height as u16).to_le_bytes());
+        out.push(0);
+
+        let mut indices: Vec<u8> = Vec::with_capacity(width * height);
+        for yy in 0..height {
+            for xx in 0..width {
+                indices.push(frame.get_pixel(xx, yy) as u8);
+            }
+        }
+
+        out.push(min_code_size);
+        for block in lzw_encode(&indices, min_code_size).chunks(GIF_MAX_SUB_BLOCK) {
+            out.push(block.len() as u8);
+            out.extend_from_slice(block);
+        }
+        out.push(0); // block terminator
+    }
+
+    out.push(0x3B); // trailer
+    Ok(out)
+}
+
+// Packs variable width codes LSB first, as GIF requires.
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    bit_count: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.bits |= (code as u32) << self.bit_count;
+        self.bit_count += size;
+        while self.bit_count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+        self.bytes
+    }
+}
+
+/// GIF variant of LZW: starts with a clear code, grows the code size as the
+/// table fills, and emits a clear code to reset once 4096 codes are in use.
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear_code: u16 = 1 << min_code_size;
+    let end_code: u16 = clear_code + 1;
+
+    let mut writer = BitWriter {
+        bytes: Vec::new(),
+        bits: 0,
+        bit_count: 0,
+    };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = min_code_size + 1;
+    let mut next_code = end_code + 1;
+
+    writer.write(clear_code, code_size);
+    let Some((&first, rest)) = indices.split_first() else {
+        writer.write(end_code, code_size);
+        return writer.finish();
+    };
+
+    let mut prefix = first as u16;
+    for &pixel in rest {
+        if let Some(&code) = table.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+
+        writer.write(prefix, code_size);
+        if next_code < GIF_MAX_CODES {
+            table.insert((prefix, pixel), next_code);
+            next_code += 1;
+            // the decoder widens its codes once its table reaches this size
+            if next_code > (1 << code_size) && code_size < GIF_MAX_CODE_SIZE {
+                code_size += 1;
+            }
+        } else {
+            writer.write(clear_code, code_size);
+            table.clear();
+            code_size = min_code_size + 1;
+            next_code = end_code + 1;
+        }
+        prefix = pixel as u16;
+    }
+
+    writer.write(prefix, code_size);
+    writer.write(end_code, code_size);
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::colors::RGB4;
+
+    // Reference GIF LZW decoder, used to check the encoder round-trips.
+    fn lzw_decode(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear_code = 1usize << min_code_size;
+        let end_code = clear_code + 1;
+        let mut out = Vec::new();
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut code_size = min_code_size + 1;
+        let mut prev: Option<Vec<u8>> = None;
+        let (mut bits, mut bit_count, mut pos) = (0u32, 0u8, 0usize);
+
+        loop {
+            while bit_count < code_size {
+                bits |= (data[pos] as u32) << bit_count;
+                pos += 1;
+                bit_count += 8;
+            }
+            let code = (bits & ((1 << code_size) - 1)) as usize;
+            bits >>= code_size;
+            bit_count -= code_size;
+
+            if code == clear_code {
+                table = (0..clear_code).map(|c| vec![c as u8]).collect();
+                table.push(Vec::new());
+                table.push(Vec::new());
+                code_size = min_code_size + 1;
+                prev = None;
+                continue;
+            }
+            if code == end_code {
+                return out;
+            }
+
+            let entry = match (&prev, table.get(code)) {
+                (_, Some(entry)) => entry.clone(),
+                (Some(p), None) => [p.clone(), vec![p[0]]].concat(),
+                (None, None) => panic!("bad first code {code}"),
+            };
+            out.extend_from_slice(&entry);
+            if let Some(p) = prev {
+                if table.len() < 4096 {
+                    table.push([p, vec![entry[0]]].concat());
+                    if table.len() == (1 << code_size) && code_size < 12 {
+                        code_size += 1;
+                    }
+                }
+            }
+            prev = Some(entry);
+        }
+    }
+
+    fn frame(width: usize, height: usize, seed: usize) -> BitMap {
+        let mut bitmap = BitMap::build(width, height, 2).unwrap();
+        for yy in 0..height {
+            for xx in 0..width {
+                bitmap.set_pixel(xx, yy, (xx / 3 + yy + seed) % 4);
+            }
+        }
+        bitmap
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        // long enough to fill the table and force a clear code
+        let indices: Vec<u8> = (0..20_000u32)
+            .map(|i| ((i * 7 + i / 13) % 16) as u8)
+            .collect();
+        let encoded = lzw_encode(&indices, 4);
+        assert_eq!(lzw_decode(&encoded, 4), indices);
+
+        let encoded = lzw_encode(&[1, 1, 1, 1, 0, 1], 2);
+        assert_eq!(lzw_decode(&encoded, 2), vec![1, 1, 1, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_animated_gif_frames_and_size() {
+        let palette = Palette {
+            colors: vec![
+                RGB4::from(0x000),
+                RGB4::from(0xF00),
+                RGB4::from(0x0F0),
+                RGB4::from(0x00F),
+            ],
+        };
+        let frames = vec![frame(17, 9, 0), frame(17, 9, 1)];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("anim.gif");
+        save_animated_gif(&frames, &palette, &[10, 20], Some(0), &path).unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(&data[0..6], b"GIF89a");
+        assert_eq!(u16::from_le_bytes([data[6], data[7]]), 17);
+        assert_eq!(u16::from_le_bytes([data[8], data[9]]), 9);
+        let table_size = 2usize << (data[10] & 0x07);
+        assert_eq!(table_size, 4);
+        assert_eq!(&data[13..16], &[0, 0, 0]);
+        assert_eq!(&data[16..19], &[0xFF, 0, 0]);
+
+        // walk the block stream, decoding every image
+        let mut offset = 13 + table_size * 3;
+        let mut images: Vec<(u16, u16, Vec<u8>)> = Vec::new();
+        let mut delays: Vec<u16> = Vec::new();
+        loop {
+            match data[offset] {
+                0x21 => {
+                    if data[offset + 1] == 0xF9 {
+                        assert_eq!(data[offset + 3] & 0x01, 1); // transparency flag
+                        delays.push(u16::from_le_bytes([data[offset + 4], data[offset + 5]]));
+                    }
+                    offset += 2;
+                    while data[offset] != 0 {
+                        offset += data[offset] as usize + 1;
+                    }
+                    offset += 1;
+                }
+                0x2C => {
+                    let w = u16::from_le_bytes([data[offset + 5], data[offset + 6]]);
+                    let h = u16::from_le_bytes([data[offset + 7], data[offset + 8]]);
+                    let min_code_size = data[offset + 10];
+                    offset += 11;
+                    let mut lzw = Vec::new();
+                    while data[offset] != 0 {
+                        let len = data[offset] as usize;
+                        lzw.extend_from_slice(&data[offset + 1..offset + 1 + len]);
+                        offset += len + 1;
+                    }
+                    offset += 1;
+                    images.push((w, h, lzw_decode(&lzw, min_code_size)));
+                }
+                0x3B => break,
+                other => panic!("unexpected block {other:#x} at {offset}"),
+            }
+        }
+
+        assert_eq!(images.len(), 2);
+        assert_eq!(delays, vec![10, 20]);
+        for (frame_bitmap, (w, h, pixels)) in frames.iter().zip(images.iter()) {
+            assert_eq!((*w, *h), (17, 9));
+            assert_eq!(pixels.len(), 17 * 9);
+            assert_eq!(pixels[17 + 5] as usize, frame_bitmap.get_pixel(5, 1));
+        }
+    }
+
+    #[test]
+    fn test_mismatched_frame_sizes_rejected() {
+        let palette = Palette { colors: Vec::new() };
+        let frames = vec![frame(8, 8, 0), frame(9, 8, 0)];
+        assert!(encode_gif(&frames, &palette, &[], None).is_err());
+        assert!(encode_gif(&[], &palette, &[], None).is_err());
+    }
+
+    #[test]
+    fn test_deep_frames_rejected() {
+        let palette = Palette { colors: Vec::new() };
+        let mut deep = frame(8, 8, 0);
+        deep.depth = 9;
+        let frames = vec![frame(8, 8, 0), deep];
+        assert!(encode_gif(&frames, &palette, &[], None).is_err());
+    }
+}
@@ -58,6 +58,7 @@ pub struct Hunk {
 pub struct HunkData {
     pub header: HunkHeader,
     pub hunks: Vec<Hunk>,
+    pub trailer: Vec<u8>, // anything appended after the final HUNK_END, usually empty
 }
 
 pub fn load_hunkfile(filepath: &Path) -> Result<HunkData, String> {
@@ -83,6 +84,7 @@ pub fn load_hunkfile(filepath: &Path) -> Result<HunkData, String> {
             hunk_sizes: Vec::new(),
         },
         hunks: Vec::new(),
+        trailer: Vec::new(),
     };
 
     // HUNK_HEADER structure:
@@ -124,6 +126,7 @@ pub fn load_hunkfile(filepath: &Path) -> Result<HunkData, String> {
     // println!("Hunk Header: {:?}", hunk.header);
 
     let mut hunk_index: usize = hunk.header.first_hunk as usize;
+    let mut ends_seen: usize = 0;
 
     'hunkloop: loop {
         if offset >= file_data.len() {
@@ -178,6 +181,10 @@ pub fn load_hunkfile(filepath: &Path) -> Result<HunkData, String> {
                 data,
             });
 
+            hunk_index += 1;
+        } else if hunk_id == HUNK_BSS {
+            // no data on disk, just the size of zeroed memory to allocate
+            let _size = try_read_u32(&file_data, &mut offset)?;
             hunk_index += 1;
         } else if hunk_id == HUNK_RELOC32 {
             /*
@@ -221,10 +228,170 @@ pub fn load_hunkfile(filepath: &Path) -> Result<HunkData, String> {
                     // since we're indexing into an array instead of memory, we don't need to do anything special
                 }
             }
+        } else if hunk_id == HUNK_ABSRELOC16 {
+            /*
+             * ABSRELOC16 block structure, same layout as RELOC32SHORT:
+             * repeated:
+             * WORD - N offsets, if zero then end of relo lists
+             * WORD - hunk number for relocations
+             * WORD[N] - offsets of 16 bit absolute references to process
+             * then padded with one WORD if needed to end on a LONG boundary
+             */
+            let block_start = offset;
+            'reloloop: loop {
+                let count = try_read_u16(&file_data, &mut offset)?;
+                if count == 0 {
+                    break 'reloloop;
+                }
+                let hunk_num = try_read_u16(&file_data, &mut offset)? as usize;
+                if hunk_num >= hunk.hunks.len() {
+                    return Err(format!(
+                        "{:?}: ABSRELOC16 references hunk {} but only {} hunks loaded",
+                        filepath,
+                        hunk_num,
+                        hunk.hunks.len()
+                    ));
+                }
+                let hunk_len = hunk.hunks[hunk_num].data.len();
+
+                for _index in 0..count as usize {
+                    let rel_offset = try_read_u16(&file_data, &mut offset)? as usize;
+                    if rel_offset + 2 > hunk_len {
+                        return Err(format!(
+                            "{:?}: ABSRELOC16 offset {} + 2 exceeds hunk {} data length {}",
+                            filepath, rel_offset, hunk_num, hunk_len
+                        ));
+                    }
+                    // as with RELOC32, indexing into an array means there is nothing to patch
+                }
+            }
+            if !(offset - block_start).is_multiple_of(4) {
+                offset += 2;
+            }
         } else if hunk_id == HUNK_END {
-            break 'hunkloop;
+            // every hunk (CODE, DATA or BSS) is closed by its own HUNK_END; once
+            // the last one has been read, whatever follows is appended data
+            ends_seen += 1;
+            if ends_seen >= hunk_count {
+                hunk.trailer = file_data[offset..].to_vec();
+                break 'hunkloop;
+            }
         }
     }
 
     Ok(hunk)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_longs(file: &mut Vec<u8>, longs: &[u32]) {
+        for long in longs {
+            file.extend_from_slice(&long.to_be_bytes());
+        }
+    }
+
+    fn push_words(file: &mut Vec<u8>, words: &[u16]) {
+        for word in words {
+            file.extend_from_slice(&word.to_be_bytes());
+        }
+    }
+
+    // Header for two 4-LONG DATA hunks, followed by the first hunk only.
+    fn first_data_hunk() -> Vec<u8> {
+        let mut file = Vec::new();
+        push_longs(&mut file, &[MAGIC_COOKIE, 0, 2, 0, 1, 4, 4]);
+        push_longs(
+            &mut file,
+            &[HUNK_DATA, 4, 0x11111111, 0x22222222, 0x33333333, 0],
+        );
+        file
+    }
+
+    // Second DATA hunk plus HUNK_END; only found if the stream is still in sync.
+    fn push_second_hunk_and_end(file: &mut Vec<u8>) {
+        push_longs(file, &[HUNK_DATA, 4, 0x55555555, 0x66666666, 0x77777777, 0]);
+        push_longs(file, &[HUNK_END]);
+    }
+
+    fn load_bytes(file: &[u8]) -> Result<HunkData, String> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.hunk");
+        std::fs::write(&path, file).unwrap();
+        load_hunkfile(&path)
+    }
+
+    #[test]
+    fn test_absreloc16_with_padding() {
+        let mut file = first_data_hunk();
+        // count, hunk, 2 offsets, terminator = 5 words, padded to a LONG boundary
+        push_longs(&mut file, &[HUNK_ABSRELOC16]);
+        push_words(&mut file, &[2, 0, 0x0002, 0x0006, 0, 0]);
+        push_second_hunk_and_end(&mut file);
+
+        let hunk = load_bytes(&file).unwrap();
+        assert_eq!(hunk.hunks.len(), 2);
+        assert_eq!(&hunk.hunks[1].data[0..4], &[0x55, 0x55, 0x55, 0x55]);
+    }
+
+    #[test]
+    fn test_absreloc16_without_padding() {
+        let mut file = first_data_hunk();
+        // count, hunk, 1 offset, terminator = 4 words, already LONG aligned
+        push_longs(&mut file, &[HUNK_ABSRELOC16]);
+        push_words(&mut file, &[1, 0, 0x0004, 0]);
+        push_second_hunk_and_end(&mut file);
+
+        let hunk = load_bytes(&file).unwrap();
+        assert_eq!(hunk.hunks.len(), 2);
+        assert_eq!(&hunk.hunks[1].data[0..4], &[0x55, 0x55, 0x55, 0x55]);
+    }
+
+    #[test]
+    fn test_trailer_after_final_hunk_end() {
+        let mut file = first_data_hunk();
+        push_longs(&mut file, &[HUNK_END]);
+        push_second_hunk_and_end(&mut file);
+        file.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let hunk = load_bytes(&file).unwrap();
+        assert_eq!(hunk.hunks.len(), 2);
+        assert_eq!(hunk.trailer, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_trailer_after_bss_hunk() {
+        let mut file = Vec::new();
+        push_longs(&mut file, &[MAGIC_COOKIE, 0, 2, 0, 1, 4, 8]);
+        push_longs(
+            &mut file,
+            &[HUNK_DATA, 4, 0x11111111, 0x22222222, 0x33333333, 0],
+        );
+        push_longs(&mut file, &[HUNK_END, HUNK_BSS, 8, HUNK_END]);
+        file.extend_from_slice(&[9, 8, 7, 6]);
+
+        let hunk = load_bytes(&file).unwrap();
+        assert_eq!(hunk.hunks.len(), 1);
+        assert_eq!(hunk.trailer, vec![9, 8, 7, 6]);
+    }
+
+    #[test]
+    fn test_no_trailer() {
+        let mut file = first_data_hunk();
+        push_second_hunk_and_end(&mut file);
+
+        let hunk = load_bytes(&file).unwrap();
+        assert!(hunk.trailer.is_empty());
+    }
+
+    #[test]
+    fn test_absreloc16_bad_hunk_number() {
+        let mut file = first_data_hunk();
+        push_longs(&mut file, &[HUNK_ABSRELOC16]);
+        push_words(&mut file, &[1, 3, 0x0004, 0]);
+        push_second_hunk_and_end(&mut file);
+
+        assert!(load_bytes(&file).is_err());
+    }
+}
@@ -2,6 +2,7 @@
 
 use serde::Deserialize;
 
+use crate::game::bitmap::BitMap;
 use crate::game::byteops::*;
 use crate::game::colors::Palette;
 use crate::game::colors::RGB4;
@@ -24,10 +25,18 @@ pub struct ImageAsset {
  */
 
 const FOURCC_FORM: u32 = 0x464F524D; // 'FORM'
+const FOURCC_CAT: u32 = 0x43415420; // 'CAT '
+const FOURCC_LIST: u32 = 0x4C495354; // 'LIST'
 const FOURCC_ILBM: u32 = 0x494C424D; // 'ILBM'
 const FOURCC_BMHD: u32 = 0x424D4844; // 'BMHD'
 const FOURCC_CMAP: u32 = 0x434D4150; // 'CMAP'
 const FOURCC_BODY: u32 = 0x424F4459; // 'BODY'
+const FOURCC_DPI: u32 = 0x44504920; // 'DPI '
+const FOURCC_CAMG: u32 = 0x43414D47; // 'CAMG'
+
+// Amiga display mode bits in a CAMG chunk
+const CAMG_EXTRA_HALFBRITE: u32 = 0x0080;
+const CAMG_HAM: u32 = 0x0800;
 
 const MASK_NONE: u8 = 0;
 const MASK_HAS_MASK: u8 = 1;
@@ -37,14 +46,150 @@ const MASK_LASSO: u8 = 3;
 const COMPRESSION_NONE: u8 = 0;
 const COMPRESSION_BYTE_RUN1: u8 = 1;
 
+/// Upper bounds on the image size a BMHD may declare, checked before any
+/// pixel data is allocated so a corrupt or hostile file can't request a
+/// huge buffer.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageLimits {
+    pub max_dimension: usize, // largest width or height
+    pub max_pixels: usize,    // largest width * height
+    pub max_bitplanes: usize,
+}
+
+impl Default for ImageLimits {
+    fn default() -> Self {
+        ImageLimits {
+            max_dimension: 8192,
+            max_pixels: 8192 * 8192,
+            max_bitplanes: 24,
+        }
+    }
+}
+
+impl ImageLimits {
+    fn check(&self, width: usize, height: usize, bitplanes: usize) -> Result<(), String> {
+        if width > self.max_dimension || height > self.max_dimension {
+            return Err(format!(
+                "ILBM size {}x{} exceeds the {} pixel dimension limit",
+                width, height, self.max_dimension
+            ));
+        }
+        if width * height > self.max_pixels {
+            return Err(format!(
+                "ILBM size {}x{} exceeds the {} pixel limit",
+                width, height, self.max_pixels
+            ));
+        }
+        if bitplanes > self.max_bitplanes {
+            return Err(format!(
+                "ILBM has {} bitplanes, limit is {}",
+                bitplanes, self.max_bitplanes
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Size of an image's BODY on disk versus after ByteRun1 decoding, for
+/// tracking how well assets compress.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BodyStats {
+    pub compressed: bool,
+    pub disk_bytes: usize,    // BODY chunk size in the file
+    pub decoded_bytes: usize, // BODY size after decompression
+}
+
+impl BodyStats {
+    /// Decoded size over on-disk size; 1.0 for uncompressed or empty bodies.
+    pub fn ratio(&self) -> f64 {
+        if self.disk_bytes == 0 {
+            return 1.0;
+        }
+        self.decoded_bytes as f64 / self.disk_bytes as f64
+    }
+}
+
+/// How an image's decoded `pixels` are to be interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageKind {
+    /// Interleaved bitplanes holding palette indices.
+    #[default]
+    Indexed,
+    /// 6 bitplanes in Extra Half-Brite mode: indices 32..63 are half-bright
+    /// copies of colors 0..31.
+    Ehb,
+    /// 6 bitplanes in Hold-And-Modify mode; only the low 4 bits index the
+    /// palette.
+    Ham6,
+    /// Deep ILBM, more than 8 bitplanes of direct RGB (see
+    /// `BitMap::from_channels` for the plane order).
+    DirectRgb,
+    /// One byte per pixel (PBM). No decoder produces this yet.
+    Chunky,
+}
+
+// Colors set in a CMAP for an Extra Half-Brite image; the hardware derives
+// the other 32 from them.
+const EHB_BASE_COLORS: usize = 32;
+
+// Classify an ILBM from its depth, CAMG display mode and CMAP size. Without
+// a CAMG chunk, 6 planes with a 32 color CMAP can only sensibly be EHB.
+fn image_kind(bitplanes: usize, camg: Option<u32>, cmap_len: usize) -> ImageKind {
+    let mode = camg.unwrap_or(0);
+    if bitplanes > 8 {
+        ImageKind::DirectRgb
+    } else if bitplanes == 6 && mode & CAMG_HAM != 0 {
+        ImageKind::Ham6
+    } else if bitplanes == 6
+        && (mode & CAMG_EXTRA_HALFBRITE != 0 || (camg.is_none() && cmap_len == EHB_BASE_COLORS))
+    {
+        ImageKind::Ehb
+    } else {
+        ImageKind::Indexed
+    }
+}
+
+// Location and encoding of a FORM's BODY chunk within the file data.
+#[derive(Debug, Clone, Copy)]
+struct BodyChunk {
+    offset: usize,
+    size: usize,
+    compressed: bool,
+    masking: u8,
+}
+
+/// Everything `IffImage` holds except the pixel data, returned by
+/// `IffImage::load_into` which decodes the pixels into a caller's buffer.
+#[derive(Debug, Clone)]
+pub struct IffImageMeta {
+    pub width: usize,
+    pub height: usize,
+    pub bitplanes: usize,
+    pub kind: ImageKind,
+    pub is_ehb: bool,
+    pub colormap: Option<Palette>,
+    pub transparent_color: Option<usize>,
+    pub dpi: Option<(u16, u16)>,
+    pub colormap_padded: bool,
+    pub colormap_rgb24: Option<Vec<(u8, u8, u8)>>,
+    pub body_stats: BodyStats,
+}
+
 #[derive(Debug)]
 pub struct IffImage {
     pub width: usize,
     pub height: usize,
     pub bitplanes: usize,
+    pub kind: ImageKind, // how to interpret `pixels`, set during decode
+    pub is_ehb: bool,    // kind is Ehb; the colormap holds all 64 colors
     pub colormap: Option<Palette>,
     pub transparent_color: Option<usize>,
     pub pixels: Vec<u8>,
+    pub dpi: Option<(u16, u16)>, // authoring dots per inch (x, y), metadata only
+    pub colormap_padded: bool,   // CMAP had fewer than 1 << bitplanes entries
+    // full 8-bit CMAP colors, only kept for AGA-style CMAPs (see `is_aga_cmap`)
+    pub colormap_rgb24: Option<Vec<(u8, u8, u8)>>,
+    body_stats: BodyStats,
 }
 
 impl IffImage {
@@ -64,16 +209,115 @@ impl IffImage {
         Ok(result.unwrap())
     }
 
-    pub fn load_from_data(input_data: &Vec<u8>) -> Result<IffImage, String> {
-        let mut offset: usize = 0;
+    pub fn load_from_data(input_data: &[u8]) -> Result<IffImage, String> {
+        let mut pixels: Vec<u8> = Vec::new();
+        let meta = IffImage::load_into(input_data, &mut pixels)?;
+        Ok(IffImage::from_parts(meta, pixels))
+    }
+
+    /// Decode an ILBM with its pixels written to `scratch` (cleared first)
+    /// instead of a new buffer, so loading many small brushes can reuse one
+    /// allocation. On error `scratch` is left empty.
+    pub fn load_into(input_data: &[u8], scratch: &mut Vec<u8>) -> Result<IffImageMeta, String> {
+        let buffer = std::mem::take(scratch);
+        let (image, _) =
+            IffImage::parse_chunks(input_data, 0, &ImageLimits::default(), true, buffer)?;
+        let (meta, pixels) = image.into_parts();
+        *scratch = pixels;
+        Ok(meta)
+    }
+
+    fn from_parts(meta: IffImageMeta, pixels: Vec<u8>) -> IffImage {
+        IffImage {
+            width: meta.width,
+            height: meta.height,
+            bitplanes: meta.bitplanes,
+            kind: meta.kind,
+            is_ehb: meta.is_ehb,
+            colormap: meta.colormap,
+            transparent_color: meta.transparent_color,
+            pixels,
+            dpi: meta.dpi,
+            colormap_padded: meta.colormap_padded,
+            colormap_rgb24: meta.colormap_rgb24,
+            body_stats: meta.body_stats,
+        }
+    }
+
+    fn into_parts(self) -> (IffImageMeta, Vec<u8>) {
+        let meta = IffImageMeta {
+            width: self.width,
+            height: self.height,
+            bitplanes: self.bitplanes,
+            kind: self.kind,
+            is_ehb: self.is_ehb,
+            colormap: self.colormap,
+            transparent_color: self.transparent_color,
+            dpi: self.dpi,
+            colormap_padded: self.colormap_padded,
+            colormap_rgb24: self.colormap_rgb24,
+            body_stats: self.body_stats,
+        };
+        (meta, self.pixels)
+    }
+
+    /// `load_from_data` with caller supplied size limits.
+    pub fn load_from_data_with_limits(
+        input_data: &[u8],
+        limits: &ImageLimits,
+    ) -> Result<IffImage, String> {
+        IffImage::parse(input_data, 0, limits)
+    }
+
+    /// `load_from_data` for brushes that use a transparent index by
+    /// convention without saying so in the BMHD. If the file declares no
+    /// transparent color, `assume_transparent_index` (when set) is used as
+    /// `transparent_color`; a declared one always wins.
+    pub fn load_from_data_opts(
+        input_data: &[u8],
+        assume_transparent_index: Option<usize>,
+    ) -> Result<IffImage, String> {
+        let mut image = IffImage::parse(input_data, 0, &ImageLimits::default())?;
+        if image.transparent_color.is_none() {
+            image.transparent_color = assume_transparent_index;
+        }
+        Ok(image)
+    }
+
+    /// Load an ILBM whose FORM header begins at `start` within `input_data`,
+    /// e.g. one embedded in an archive, without copying it out first.
+    pub fn load_from_data_at(input_data: &[u8], start: usize) -> Result<IffImage, String> {
+        IffImage::parse(input_data, start, &ImageLimits::default())
+    }
+
+    fn parse(input_data: &[u8], start: usize, limits: &ImageLimits) -> Result<IffImage, String> {
+        IffImage::parse_chunks(input_data, start, limits, true, Vec::new()).map(|(image, _)| image)
+    }
+
+    // Parse the FORM at `start`, also returning where its BODY chunk is; a
+    // FORM without a BODY is an error. With
+    // `decode_body` false the BODY is located but left undecoded, and
+    // `pixels` stays empty. Pixels are decoded into `buffer`, reusing its
+    // allocation.
+    fn parse_chunks(
+        input_data: &[u8],
+        start: usize,
+        limits: &ImageLimits,
+        decode_body: bool,
+        mut buffer: Vec<u8>,
+    ) -> Result<(IffImage, BodyChunk), String> {
+        buffer.clear();
+        let mut offset: usize = start;
 
         // read the FORM header
-        let form_id = read_u32(input_data, &mut offset);
+        let form_id = try_read_u32(input_data, &mut offset)?;
         if form_id != FOURCC_FORM {
-            return Err("Missing FORM header".to_string());
+            return Err(format!("Missing FORM header at offset {}", start));
         }
-        let _form_size = read_u32(input_data, &mut offset); // don't really care about this
-        let form_type = read_u32(input_data, &mut offset);
+        let form_size = try_read_u32(input_data, &mut offset)? as usize;
+        // stop at the end of this FORM, anything after it belongs to the enclosing file
+        let form_end = (start + 8).saturating_add(form_size).min(input_data.len());
+        let form_type = try_read_u32(input_data, &mut offset)?;
         if form_type != FOURCC_ILBM {
             return Err("FORM type is not ILBM".to_string());
         }
@@ -82,32 +326,39 @@ impl IffImage {
             width: 0,
             height: 0,
             bitplanes: 0,
+            kind: ImageKind::Indexed,
+            is_ehb: false,
             colormap: None,
             transparent_color: None,
-            pixels: Vec::new(),
+            pixels: buffer,
+            dpi: None,
+            colormap_padded: false,
+            colormap_rgb24: None,
+            body_stats: BodyStats::default(),
         };
 
         let mut compressed = false;
+        let mut masking = MASK_NONE;
+        let mut camg: Option<u32> = None;
+        let mut body: Option<BodyChunk> = None;
 
         // now read chunks until we find BMHD, CMAP, and BODY, skipping any unknown chunks
-        while offset < input_data.len() {
-            let chunk_id = read_u32(&input_data, &mut offset);
-            let chunk_size = read_u32(&input_data, &mut offset) as usize;
+        while offset < form_end {
+            let chunk_id = try_read_u32(input_data, &mut offset)?;
+            let chunk_size = try_read_u32(input_data, &mut offset)? as usize;
 
             match chunk_id {
                 FOURCC_BMHD => {
                     // read bitmap header
                     let mut header_offset = offset;
-                    image.width = read_u16(&input_data, &mut header_offset) as usize;
-                    image.height = read_u16(&input_data, &mut header_offset) as usize;
+                    image.width = try_read_u16(input_data, &mut header_offset)? as usize;
+                    image.height = try_read_u16(input_data, &mut header_offset)? as usize;
                     header_offset += 4; // skip x,y position
-                    image.bitplanes = input_data[header_offset] as usize;
-                    header_offset += 1;
-
-                    let masking = input_data[header_offset];
-                    header_offset += 1;
+                    image.bitplanes = try_read_u8(input_data, &mut header_offset)? as usize;
+                    limits.check(image.width, image.height, image.bitplanes)?;
 
-                    let compression = input_data[header_offset];
+                    masking = try_read_u8(input_data, &mut header_offset)?;
+                    let compression = try_read_u8(input_data, &mut header_offset)?;
                     match compression {
                         COMPRESSION_NONE => {}
                         COMPRESSION_BYTE_RUN1 => {
@@ -120,11 +371,12 @@ impl IffImage {
                             ));
                         }
                     }
-                    header_offset += 1; // skip pad byte
 
-                    // get transparent color if present
+                    // get transparent color if present, the WORD after the pad byte
                     if masking == MASK_HAS_TRANSPARENCY {
-                        let transparent_color = read_u16(&input_data, &mut header_offset) as usize;
+                        let mut transparent_offset = offset + 12;
+                        let transparent_color =
+                            try_read_u16(input_data, &mut transparent_offset)? as usize;
                         image.transparent_color = Some(transparent_color);
                     } else {
                         image.transparent_color = None;
@@ -135,32 +387,65 @@ impl IffImage {
                 FOURCC_CMAP => {
                     // read colormap
                     let mut colormap = Palette { colors: Vec::new() };
+                    let mut rgb24: Vec<(u8, u8, u8)> = Vec::with_capacity(chunk_size / 3);
                     for _ in 0..(chunk_size / 3) {
-                        colormap.colors.push(RGB4::from((
+                        let rgb = (
                             input_data[offset],
                             input_data[offset + 1],
                             input_data[offset + 2],
-                        )));
+                        );
+                        colormap.colors.push(RGB4::from(rgb));
+                        rgb24.push(rgb);
                         offset += 3;
                     }
                     image.colormap = Some(colormap);
+                    image.colormap_rgb24 = is_aga_cmap(&rgb24).then_some(rgb24);
+                }
+                FOURCC_CAMG => {
+                    let mut camg_offset = offset;
+                    camg = Some(try_read_u32(input_data, &mut camg_offset)?);
+                    offset += chunk_size;
+                }
+                FOURCC_DPI => {
+                    let mut dpi_offset = offset;
+                    let dpi_x = try_read_u16(input_data, &mut dpi_offset)?;
+                    let dpi_y = try_read_u16(input_data, &mut dpi_offset)?;
+                    image.dpi = Some((dpi_x, dpi_y));
+                    offset += chunk_size;
                 }
                 FOURCC_BODY => {
+                    image.body_stats = BodyStats {
+                        compressed,
+                        disk_bytes: chunk_size,
+                        decoded_bytes: chunk_size,
+                    };
+                    body = Some(BodyChunk {
+                        offset,
+                        size: chunk_size,
+                        compressed,
+                        masking,
+                    });
                     // read body data
-                    if !compressed {
+                    if !decode_body {
+                        offset += chunk_size;
+                    } else if !compressed {
                         // uncompressed, just read the data
                         let pixels = input_data.get(offset..offset + chunk_size);
                         if pixels.is_none() {
                             return Err("BODY chunk in ILBM is truncated".to_string());
                         }
-                        image.pixels.clear();
-                        image.pixels.extend(pixels.unwrap());
+                        let mut pixel_data = std::mem::take(&mut image.pixels);
+                        pixel_data.clear();
+                        pixel_data.extend_from_slice(pixels.unwrap());
+                        image.normalize_body(&mut pixel_data, masking)?;
+                        image.pixels = pixel_data;
                         offset += chunk_size;
-                        continue;
                     } else {
                         // compressed with ByteRun1
                         let mut body_offset: usize = 0;
-                        let mut pixel_data: Vec<u8> = Vec::with_capacity(
+                        let mut pixel_data = std::mem::take(&mut image.pixels);
+                        pixel_data.clear();
+                        pixel_data.reserve(
                             image.height * ((image.width + 15) / 16) * 2 * image.bitplanes,
                         );
                         while body_offset < chunk_size {
@@ -193,6 +478,8 @@ impl IffImage {
                                 body_offset += 1;
                             } // n == -128 is a no-op
                         }
+                        image.body_stats.decoded_bytes = pixel_data.len();
+                        image.normalize_body(&mut pixel_data, masking)?;
                         image.pixels = pixel_data;
                         offset += chunk_size;
                     }
@@ -202,12 +489,975 @@ impl IffImage {
                     offset += chunk_size;
                 }
             }
-            // offset should be on an even byte boundary
-            if offset % 2 != 0 {
+            // chunks start on an even byte boundary, relative to the FORM
+            if !(offset - start).is_multiple_of(2) {
                 offset += 1;
             }
         }
 
-        Ok(image)
+        let cmap_len = image.colormap.as_ref().map_or(0, |c| c.colors.len());
+        image.kind = image_kind(image.bitplanes, camg, cmap_len);
+        image.is_ehb = image.kind == ImageKind::Ehb;
+        image.normalize_colormap();
+        let body = body.ok_or("ILBM has no BODY chunk")?;
+        Ok((image, body))
+    }
+
+    /**
+     * Resize the colormap to exactly `1 << bitplanes` entries, padding short
+     * CMAPs with black (and setting `colormap_padded`) or dropping extra
+     * entries. Deep images (more than 8 planes) have no meaningful colormap
+     * size and are left alone.
+     *
+     * EHB images keep only the first 32 CMAP colors and append their
+     * half-bright copies as entries 32..63, halving each component as the
+     * hardware does.
+     */
+    fn normalize_colormap(&mut self) {
+        if self.bitplanes == 0 || self.bitplanes > 8 {
+            return;
+        }
+        let color_count = if self.is_ehb {
+            EHB_BASE_COLORS
+        } else {
+            1usize << self.bitplanes
+        };
+        if let Some(colormap) = self.colormap.as_mut() {
+            if colormap.colors.len() < color_count {
+                colormap.colors.resize(color_count, RGB4::from(0x000));
+                self.colormap_padded = true;
+            } else {
+                colormap.colors.truncate(color_count);
+            }
+            if self.is_ehb {
+                let half: Vec<RGB4> = colormap
+                    .colors
+                    .iter()
+                    .map(|c| RGB4::from((c.color >> 1) & 0x777))
+                    .collect();
+                colormap.colors.extend(half);
+            }
+        }
+        if let Some(rgb24) = self.colormap_rgb24.as_mut() {
+            rgb24.resize(color_count, (0, 0, 0));
+            if self.is_ehb {
+                let half: Vec<(u8, u8, u8)> = rgb24
+                    .iter()
+                    .map(|&(r, g, b)| (r >> 1, g >> 1, b >> 1))
+                    .collect();
+                rgb24.extend(half);
+            }
+        }
+    }
+
+    /**
+     * Trim decoded BODY data to exactly `height` rows of `bitplanes`
+     * interleaved planes, each padded to a whole WORD as the ILBM spec
+     * requires. A mask plane (MASK_HAS_MASK) follows the color planes in every
+     * row and is dropped so row strides line up with `to_bitmap`. Any trailing
+     * bytes past the last row (e.g. ByteRun1 padding) are dropped. Done in
+     * place, so the buffer's allocation is kept.
+     *
+     * Some tools pad plane rows to a byte instead of a WORD. A BODY too short
+     * for WORD padding but long enough for byte padding is taken to be one of
+     * those, and its rows are widened to WORD padding first.
+     */
+    fn normalize_body(&self, body: &mut Vec<u8>, masking: u8) -> Result<(), String> {
+        let row_bytes = self.width.div_ceil(16) * 2;
+        let body_planes = self.bitplanes + if masking == MASK_HAS_MASK { 1 } else { 0 };
+        let expected = row_bytes * body_planes * self.height;
+        if body.len() < expected {
+            let byte_row_bytes = self.width.div_ceil(8);
+            let plane_rows = body_planes * self.height;
+            if byte_row_bytes == row_bytes || body.len() < byte_row_bytes * plane_rows {
+                return Err(format!(
+                    "BODY chunk has {} bytes, expected {} for a {}x{}x{} image ({} if rows are byte padded)",
+                    body.len(),
+                    expected,
+                    self.width,
+                    self.height,
+                    self.bitplanes,
+                    byte_row_bytes * plane_rows
+                ));
+            }
+            // rows only ever move towards the back, so widen them last to first
+            body.resize(expected, 0);
+            for row in (0..plane_rows).rev() {
+                let src = row * byte_row_bytes;
+                body.copy_within(src..src + byte_row_bytes, row * row_bytes);
+                body[row * row_bytes + byte_row_bytes..(row + 1) * row_bytes].fill(0);
+            }
+        }
+
+        // rows only ever move towards the front, so copying in order is safe
+        let kept = row_bytes * self.bitplanes;
+        let stride = row_bytes * body_planes;
+        for row in 0..self.height {
+            body.copy_within(row * stride..row * stride + kept, row * kept);
+        }
+        body.truncate(kept * self.height);
+        Ok(())
+    }
+
+    /**
+     * Load every ILBM image in an IFF file. A `CAT ` or `LIST` container is
+     * walked one level deep and each nested ILBM `FORM` is decoded in order;
+     * other nested chunks (PROP, non-ILBM FORMs) are skipped. A plain ILBM
+     * file yields a single image.
+     */
+    pub fn load_all_from_data(input_data: &[u8]) -> Result<Vec<IffImage>, String> {
+        let mut offset: usize = 0;
+        let container_id = try_read_u32(input_data, &mut offset)?;
+        if container_id == FOURCC_FORM {
+            return Ok(vec![IffImage::load_from_data_at(input_data, 0)?]);
+        }
+        if container_id != FOURCC_CAT && container_id != FOURCC_LIST {
+            return Err("Missing FORM, CAT or LIST header".to_string());
+        }
+        let container_size = try_read_u32(input_data, &mut offset)? as usize;
+        let _contents_type = try_read_u32(input_data, &mut offset)?; // hint only, often 'ILBM' or '    '
+        let container_end = (8 + container_size).min(input_data.len());
+
+        let mut images: Vec<IffImage> = Vec::new();
+        while offset + 8 <= container_end {
+            let chunk_start = offset;
+            let chunk_id = try_read_u32(input_data, &mut offset)?;
+            let chunk_size = try_read_u32(input_data, &mut offset)? as usize;
+            let chunk_end = offset + chunk_size;
+            if chunk_end > container_end {
+                return Err("Nested chunk in IFF container is truncated".to_string());
+            }
+
+            if chunk_id == FOURCC_FORM {
+                let form_type = try_read_u32(input_data, &mut offset)?;
+                if form_type == FOURCC_ILBM {
+                    images.push(IffImage::load_from_data_at(input_data, chunk_start)?);
+                }
+            }
+
+            // nested chunks are padded to an even byte boundary
+            offset = chunk_end + (chunk_end % 2);
+        }
+
+        Ok(images)
+    }
+
+    /// On-disk and decoded BODY sizes recorded while loading.
+    pub fn body_stats(&self) -> BodyStats {
+        self.body_stats
+    }
+
+    /// Convert the interleaved BODY data into a planar [`BitMap`]. Fails if
+    /// `pixels` is too short for the image dimensions, e.g. when it is empty.
+    pub fn to_bitmap(&self) -> Result<BitMap, String> {
+        let row_bytes = self.width.div_ceil(16) * 2;
+        let expected = self.height * self.bitplanes * row_bytes;
+        if self.pixels.len() < expected {
+            return Err(format!(
+                "Image has {} bytes of pixel data, expected {} for a {}x{}x{} image",
+                self.pixels.len(),
+                expected,
+                self.width,
+                self.height,
+                self.bitplanes
+            ));
+        }
+        Ok(BitMap::with_interleaved_data(
+            self.pixels.clone(),
+            self.width,
+            self.height,
+            self.bitplanes,
+            row_bytes,
+        ))
+    }
+
+    /**
+     * Produce a nearest-neighbor downscaled RGBA32 thumbnail whose largest
+     * dimension is `max_dim`, preserving aspect ratio. Images already within
+     * `max_dim` are not enlarged. The image's transparent color, if any, is
+     * rendered as transparent black.
+     *
+     * @return tuple of (pixels, width, height); the byte stride is width * 4
+     */
+    pub fn thumbnail(&self, max_dim: usize, palette: &Palette) -> (Vec<u8>, usize, usize) {
+        let longest = self.width.max(self.height);
+        if longest == 0 || max_dim == 0 {
+            return (Vec::new(), 0, 0);
+        }
+        let (pixels, stride) = match self
+            .to_bitmap()
+            .and_then(|bitmap| bitmap.generate_rgb32(palette, self.transparent_color))
+        {
+            Ok(result) => result,
+            Err(_) => return (Vec::new(), 0, 0),
+        };
+
+        let target = max_dim.min(longest);
+        let thumb_w = (self.width * target / longest).max(1);
+        let thumb_h = (self.height * target / longest).max(1);
+
+        let mut thumb: Vec<u8> = Vec::with_capacity(thumb_w * thumb_h * 4);
+        for ty in 0..thumb_h {
+            let sy = ty * self.height / thumb_h;
+            for tx in 0..thumb_w {
+                let sx = tx * self.width / thumb_w;
+                let src = sy * stride + sx * 4;
+                thumb.extend_from_slice(&pixels[src..src + 4]);
+            }
+        }
+
+        (thumb, thumb_w, thumb_h)
+    }
+}
+
+/// Whether CMAP colors look authored for AGA's 8-bit components rather than
+/// OCS/ECS 4-bit ones. OCS exporters leave the low nibble of each byte zero
+/// or a copy of the high nibble, so any other low nibble means the extra
+/// precision is real.
+fn is_aga_cmap(colors: &[(u8, u8, u8)]) -> bool {
+    colors
+        .iter()
+        .flat_map(|&(r, g, b)| [r, g, b])
+        .any(|byte| byte & 0x0F != 0 && byte & 0x0F != byte >> 4)
+}
+
+/**
+ * Decodes single BODY rows of an ILBM on demand instead of the whole image,
+ * for large maps where only a few rows are visible. The first compressed
+ * row request walks the ByteRun1 stream once to record where every row
+ * starts; after that any row is decoded straight from its start. Runs that
+ * cross row boundaries (allowed by some encoders) are handled.
+ */
+pub struct ScanlineDecoder<'a> {
+    header: IffImage, // dimensions, colormap etc.; `pixels` is left empty
+    body: &'a [u8],
+    compressed: bool,
+    body_planes: usize,    // bitplanes plus the mask plane, if any
+    body_row_bytes: usize, // bytes of one plane row in the BODY, WORD or byte padded
+    // (run offset, bytes of that run belonging to the previous row) per row
+    row_starts: Vec<(usize, usize)>,
+}
+
+impl<'a> ScanlineDecoder<'a> {
+    pub fn new(input_data: &'a [u8]) -> Result<ScanlineDecoder<'a>, String> {
+        let (header, body) =
+            IffImage::parse_chunks(input_data, 0, &ImageLimits::default(), false, Vec::new())?;
+        let body_data = input_data
+            .get(body.offset..body.offset + body.size)
+            .ok_or("BODY chunk in ILBM is truncated")?;
+        let mask_plane = if body.masking == MASK_HAS_MASK { 1 } else { 0 };
+        let mut decoder = ScanlineDecoder {
+            body_planes: header.bitplanes + mask_plane,
+            body_row_bytes: header.width.div_ceil(16) * 2,
+            header,
+            body: body_data,
+            compressed: body.compressed,
+            row_starts: Vec::new(),
+        };
+
+        // a BODY too short for WORD padded rows but long enough for byte
+        // padded ones has byte padded rows, as in `IffImage::normalize_body`
+        let plane_rows = decoder.body_planes * decoder.header.height;
+        let byte_row_bytes = decoder.header.width.div_ceil(8);
+        let available = if decoder.compressed {
+            decoder.decoded_len()
+        } else {
+            decoder.body.len()
+        };
+        if available < decoder.body_row_bytes * plane_rows
+            && available >= byte_row_bytes * plane_rows
+        {
+            decoder.body_row_bytes = byte_row_bytes;
+        }
+        Ok(decoder)
+    }
+
+    /// The image header; `pixels` is empty.
+    pub fn header(&self) -> &IffImage {
+        &self.header
+    }
+
+    // Bytes of one plane row, padded to a whole WORD.
+    fn plane_row_bytes(&self) -> usize {
+        self.header.width.div_ceil(16) * 2
+    }
+
+    /// Decode row `row` as it appears in `IffImage::pixels`: the row's
+    /// interleaved color planes, with any mask plane dropped.
+    pub fn decode_scanline(&mut self, row: usize) -> Result<Vec<u8>, String> {
+        if row >= self.header.height {
+            return Err(format!(
+                "Scanline {} out of range for a {} row image",
+                row, self.header.height
+            ));
+        }
+        let stride = self.body_row_bytes * self.body_planes;
+        let line = if self.compressed {
+            self.decode_compressed_row(row, stride)?
+        } else {
+            self.body
+                .get(row * stride..(row + 1) * stride)
+                .ok_or("BODY chunk in ILBM is truncated")?
+                .to_vec()
+        };
+
+        // widen byte padded plane rows to WORD padding and drop the mask plane
+        let row_bytes = self.plane_row_bytes();
+        let mut scanline: Vec<u8> = Vec::with_capacity(row_bytes * self.header.bitplanes);
+        for plane in line.chunks(self.body_row_bytes).take(self.header.bitplanes) {
+            scanline.extend_from_slice(plane);
+            scanline.resize(scanline.len() + row_bytes - plane.len(), 0);
+        }
+        Ok(scanline)
+    }
+
+    fn decode_compressed_row(&mut self, row: usize, stride: usize) -> Result<Vec<u8>, String> {
+        if self.row_starts.is_empty() {
+            self.index_rows(stride)?;
+        }
+        let (mut pos, mut skip) = self.row_starts[row];
+        let mut line: Vec<u8> = Vec::with_capacity(stride);
+        while line.len() < stride {
+            let (run_len, encoded_len) = self.run_at(pos)?;
+            let take = (run_len - skip).min(stride - line.len());
+            if (self.body[pos] as i8) >= 0 {
+                let literal = pos + 1 + skip;
+                line.extend_from_slice(&self.body[literal..literal + take]);
+            } else {
+                line.extend(std::iter::repeat_n(self.body[pos + 1], take));
+            }
+            pos += encoded_len;
+            skip = 0;
+        }
+        Ok(line)
+    }
+
+    // Walk the ByteRun1 stream once, recording where each row starts.
+    fn index_rows(&mut self, stride: usize) -> Result<(), String> {
+        let mut row_starts: Vec<(usize, usize)> = Vec::with_capacity(self.header.height);
+        let (mut pos, mut produced) = (0, 0);
+        for row in 0..self.header.height {
+            let target = row * stride;
+            loop {
+                let (run_len, encoded_len) = self.run_at(pos)?;
+                if produced + run_len > target {
+                    break;
+                }
+                produced += run_len;
+                pos += encoded_len;
+            }
+            row_starts.push((pos, target - produced));
+        }
+        self.row_starts = row_starts;
+        Ok(())
+    }
+
+    // Total bytes the ByteRun1 stream decodes to, up to the first truncated run.
+    fn decoded_len(&self) -> usize {
+        let (mut pos, mut produced) = (0, 0);
+        while let Ok((run_len, encoded_len)) = self.run_at(pos) {
+            produced += run_len;
+            pos += encoded_len;
+        }
+        produced
+    }
+
+    // (decoded length, encoded length) of the ByteRun1 run at `pos`.
+    fn run_at(&self, pos: usize) -> Result<(usize, usize), String> {
+        let n = *self
+            .body
+            .get(pos)
+            .ok_or("BODY chunk in ILBM is truncated before the last scanline")?
+            as i8;
+        let (run_len, encoded_len) = match n {
+            0..=127 => (n as usize + 1, n as usize + 2),
+            -127..=-1 => ((-n) as usize + 1, 2),
+            _ => (0, 1), // -128 is a no-op
+        };
+        if pos + encoded_len > self.body.len() {
+            return Err("BODY chunk in ILBM is truncated during ByteRun1 run".to_string());
+        }
+        Ok((run_len, encoded_len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_palette() -> Palette {
+        Palette {
+            colors: vec![RGB4::from(0x000), RGB4::from(0xFFF)],
+        }
+    }
+
+    // 1-bitplane image with vertical stripes 8 pixels wide
+    fn striped_image(width: usize, height: usize) -> IffImage {
+        let row_bytes = width.div_ceil(16) * 2;
+        let mut pixels = Vec::new();
+        for _ in 0..height {
+            for xx in 0..row_bytes {
+                pixels.push(if xx % 2 == 0 { 0xFF } else { 0x00 });
+            }
+        }
+        IffImage {
+            width,
+            height,
+            bitplanes: 1,
+            kind: ImageKind::Indexed,
+            is_ehb: false,
+            colormap: None,
+            transparent_color: None,
+            pixels,
+            dpi: None,
+            colormap_padded: false,
+            colormap_rgb24: None,
+            body_stats: BodyStats::default(),
+        }
+    }
+
+    fn push_chunk(out: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+        out.extend_from_slice(id);
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        out.extend_from_slice(data);
+        if !data.len().is_multiple_of(2) {
+            out.push(0);
+        }
+    }
+
+    // Uncompressed ILBM FORM with a BMHD and an all-zero BODY.
+    fn build_ilbm(width: usize, height: usize, depth: usize) -> Vec<u8> {
+        let body = vec![0u8; width.div_ceil(16) * 2 * depth * height];
+        build_ilbm_with_body(width, height, depth, MASK_NONE, COMPRESSION_NONE, &body)
+    }
+
+    fn build_ilbm_with_body(
+        width: usize,
+        height: usize,
+        depth: usize,
+        masking: u8,
+        compression: u8,
+        body: &[u8],
+    ) -> Vec<u8> {
+        let mut bmhd = Vec::new();
+        bmhd.extend_from_slice(&(width as u16).to_be_bytes());
+        bmhd.extend_from_slice(&(height as u16).to_be_bytes());
+        bmhd.extend_from_slice(&[0; 4]); // x, y
+        bmhd.extend_from_slice(&[depth as u8, masking, compression, 0]);
+        bmhd.extend_from_slice(&[0; 2]); // transparent color
+        bmhd.extend_from_slice(&[10, 11]); // aspect
+        bmhd.extend_from_slice(&(width as u16).to_be_bytes()); // page width
+        bmhd.extend_from_slice(&(height as u16).to_be_bytes()); // page height
+
+        let mut contents = b"ILBM".to_vec();
+        push_chunk(&mut contents, b"BMHD", &bmhd);
+        push_chunk(&mut contents, b"BODY", body);
+        let mut form = Vec::new();
+        push_chunk(&mut form, b"FORM", &contents);
+        form
+    }
+
+    // Expected color index of the test pattern at (x, y), 2 bitplanes.
+    fn pattern_index(x: usize, y: usize) -> usize {
+        (x + y) % 4
+    }
+
+    // Interleaved BODY rows for the test pattern. Padding bits past `width`
+    // are deliberately set so any stride mistake shows up as wrong pixels.
+    // With `mask`, an extra all-ones mask plane follows each row's color planes.
+    fn pattern_body(width: usize, height: usize, mask: bool) -> Vec<u8> {
+        pattern_body_padded(width, height, mask, width.div_ceil(16) * 2)
+    }
+
+    // `pattern_body` with each plane row `row_bytes` long.
+    fn pattern_body_padded(width: usize, height: usize, mask: bool, row_bytes: usize) -> Vec<u8> {
+        let mut body = Vec::new();
+        for yy in 0..height {
+            for plane in 0..2 {
+                let mut row = vec![0xFFu8; row_bytes];
+                for xx in 0..width {
+                    if pattern_index(xx, yy) & (1 << plane) == 0 {
+                        row[xx >> 3] &= !(0x80 >> (xx & 7));
+                    }
+                }
+                body.extend_from_slice(&row);
+            }
+            if mask {
+                body.extend(std::iter::repeat_n(0xFF, row_bytes));
+            }
+        }
+        body
+    }
+
+    // ByteRun1 encode as literal runs only, one row's worth of bytes at a time.
+    fn byterun1_literals(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for run in data.chunks(128) {
+            out.push((run.len() - 1) as u8);
+            out.extend_from_slice(run);
+        }
+        out
+    }
+
+    fn assert_pattern(image: &IffImage) {
+        let bitmap = image.to_bitmap().unwrap();
+        for yy in 0..image.height {
+            for xx in (0..4).chain(image.width - 4..image.width) {
+                assert_eq!(
+                    bitmap.get_pixel(xx, yy),
+                    pattern_index(xx, yy),
+                    "{}-wide image at ({xx}, {yy})",
+                    image.width
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_odd_and_wide_rows() {
+        for width in [17, 320] {
+            let height = 3;
+            let body = pattern_body(width, height, false);
+            let data = build_ilbm_with_body(width, height, 2, MASK_NONE, COMPRESSION_NONE, &body);
+            assert_pattern(&IffImage::load_from_data(&data).unwrap());
+
+            let data = build_ilbm_with_body(
+                width,
+                height,
+                2,
+                MASK_NONE,
+                COMPRESSION_BYTE_RUN1,
+                &byterun1_literals(&body),
+            );
+            assert_pattern(&IffImage::load_from_data(&data).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_decode_drops_mask_plane() {
+        let body = pattern_body(17, 3, true);
+        let data = build_ilbm_with_body(17, 3, 2, MASK_HAS_MASK, COMPRESSION_NONE, &body);
+        let image = IffImage::load_from_data(&data).unwrap();
+        assert_eq!(image.pixels.len(), 4 * 2 * 3);
+        assert_pattern(&image);
+    }
+
+    // ILBM with a CMAP of `colors` entries inserted after the BMHD.
+    fn build_ilbm_with_cmap(depth: usize, colors: usize) -> Vec<u8> {
+        let mut cmap_data = Vec::new();
+        for index in 0..colors {
+            cmap_data.extend_from_slice(&[0x10 * (index as u8 % 16), 0x20, 0x30]);
+        }
+        build_ilbm_with_cmap_data(depth, &cmap_data)
+    }
+
+    fn build_ilbm_with_cmap_data(depth: usize, cmap_data: &[u8]) -> Vec<u8> {
+        let mut cmap = Vec::new();
+        push_chunk(&mut cmap, b"CMAP", cmap_data);
+
+        let mut form = build_ilbm(16, 2, depth);
+        form.splice(40..40, cmap);
+        let size = (form.len() - 8) as u32;
+        form[4..8].copy_from_slice(&size.to_be_bytes());
+        form
+    }
+
+    fn build_ilbm_with_camg(depth: usize, camg: u32) -> Vec<u8> {
+        let mut chunk = Vec::new();
+        push_chunk(&mut chunk, b"CAMG", &camg.to_be_bytes());
+
+        let mut form = build_ilbm(16, 2, depth);
+        form.splice(40..40, chunk);
+        let size = (form.len() - 8) as u32;
+        form[4..8].copy_from_slice(&size.to_be_bytes());
+        form
+    }
+
+    #[test]
+    fn test_decode_sets_image_kind() {
+        let kind = |data: Vec<u8>| IffImage::load_from_data(&data).unwrap().kind;
+        assert_eq!(kind(build_ilbm(16, 2, 5)), ImageKind::Indexed);
+        assert_eq!(kind(build_ilbm_with_camg(6, 0x0080)), ImageKind::Ehb);
+        assert_eq!(kind(build_ilbm_with_camg(6, 0x0800)), ImageKind::Ham6);
+        assert_eq!(kind(build_ilbm(16, 2, 24)), ImageKind::DirectRgb);
+        // a HAM flag on a non-6-plane image doesn't make it HAM6
+        assert_eq!(kind(build_ilbm_with_camg(5, 0x0800)), ImageKind::Indexed);
+
+        let mut pixels = Vec::new();
+        let meta = IffImage::load_into(&build_ilbm_with_camg(6, 0x0080), &mut pixels).unwrap();
+        assert_eq!(meta.kind, ImageKind::Ehb);
+    }
+
+    #[test]
+    fn test_ehb_colormap_expanded_to_half_brite() {
+        // 16x1, 6 planes: pixel 0 is index 33 (planes 0 and 5), the rest 0
+        let mut body = vec![0u8; 2 * 6];
+        body[0] = 0x80;
+        body[10] = 0x80;
+        let mut cmap_data = vec![0u8; 3 * 32];
+        cmap_data[3..6].copy_from_slice(&[0xC0, 0x80, 0x40]); // color 1 = 0xC84
+        let mut cmap = Vec::new();
+        push_chunk(&mut cmap, b"CMAP", &cmap_data);
+        let mut data = build_ilbm_with_body(16, 1, 6, MASK_NONE, COMPRESSION_NONE, &body);
+        data.splice(40..40, cmap);
+        let size = (data.len() - 8) as u32;
+        data[4..8].copy_from_slice(&size.to_be_bytes());
+
+        // no CAMG, so detected from 6 planes with a 32 color CMAP
+        let image = IffImage::load_from_data(&data).unwrap();
+        assert!(image.is_ehb);
+        assert_eq!(image.kind, ImageKind::Ehb);
+        assert_eq!(image.bitplanes, 6);
+        assert!(!image.colormap_padded);
+        let colormap = image.colormap.as_ref().unwrap();
+        assert_eq!(colormap.colors.len(), 64);
+        assert_eq!(colormap.colors[33].color, 0x642);
+
+        let bitmap = image.to_bitmap().unwrap();
+        assert_eq!(bitmap.get_pixel(0, 0), 33);
+        let (pixels, _) = bitmap.generate_rgb32(colormap, None).unwrap();
+        assert_eq!(&pixels[0..4], &[0x66, 0x44, 0x22, 0xFF]);
+
+        // a CAMG without the EHB flag overrides the heuristic
+        let mut camg = Vec::new();
+        push_chunk(&mut camg, b"CAMG", &0x8000u32.to_be_bytes());
+        data.splice(40..40, camg);
+        let size = (data.len() - 8) as u32;
+        data[4..8].copy_from_slice(&size.to_be_bytes());
+        let image = IffImage::load_from_data(&data).unwrap();
+        assert!(!image.is_ehb);
+        assert_eq!(image.colormap.unwrap().colors[33].color, 0x000);
+    }
+
+    #[test]
+    fn test_short_cmap_padded_with_black() {
+        let image = IffImage::load_from_data(&build_ilbm_with_cmap(5, 16)).unwrap();
+        let colormap = image.colormap.as_ref().unwrap();
+        assert_eq!(colormap.colors.len(), 32);
+        assert!(image.colormap_padded);
+        assert_eq!(colormap.colors[15].color, 0xF23);
+        assert_eq!(colormap.colors[16].color, 0x000);
+        assert_eq!(colormap.colors[31].color, 0x000);
+    }
+
+    #[test]
+    fn test_aga_cmap_keeps_24_bit_colors() {
+        // OCS style: low nibbles zero or copies of the high nibble
+        let ocs = [0x00, 0x11, 0xF0, 0xFF, 0x80, 0x88];
+        let image = IffImage::load_from_data(&build_ilbm_with_cmap_data(1, &ocs)).unwrap();
+        assert!(image.colormap_rgb24.is_none());
+        assert_eq!(image.colormap.unwrap().colors[1].color, 0xF88);
+
+        let aga = [0x12, 0x34, 0x56, 0xFE, 0xDC, 0xBA];
+        let image = IffImage::load_from_data(&build_ilbm_with_cmap_data(1, &aga)).unwrap();
+        assert_eq!(
+            image.colormap_rgb24,
+            Some(vec![(0x12, 0x34, 0x56), (0xFE, 0xDC, 0xBA)])
+        );
+        // the 12-bit colormap is still the truncated high nibbles
+        assert_eq!(image.colormap.unwrap().colors[0].color, 0x135);
+    }
+
+    #[test]
+    fn test_long_cmap_truncated() {
+        let image = IffImage::load_from_data(&build_ilbm_with_cmap(2, 32)).unwrap();
+        let colormap = image.colormap.as_ref().unwrap();
+        assert_eq!(colormap.colors.len(), 4);
+        assert!(!image.colormap_padded);
+        assert_eq!(colormap.colors[3].color, 0x323);
+    }
+
+    #[test]
+    fn test_dpi_chunk() {
+        let form = build_ilbm(16, 2, 1);
+        assert_eq!(IffImage::load_from_data(&form).unwrap().dpi, None);
+
+        // insert a DPI chunk after the BMHD (FORM header 12 + BMHD 8 + 20 bytes)
+        let mut dpi = Vec::new();
+        push_chunk(&mut dpi, b"DPI ", &[0, 72, 0, 144]);
+        let mut form_with_dpi = form.clone();
+        form_with_dpi.splice(40..40, dpi);
+        let size = (form_with_dpi.len() - 8) as u32;
+        form_with_dpi[4..8].copy_from_slice(&size.to_be_bytes());
+
+        let image = IffImage::load_from_data(&form_with_dpi).unwrap();
+        assert_eq!(image.dpi, Some((72, 144)));
+        assert_eq!((image.width, image.height), (16, 2));
+    }
+
+    #[test]
+    fn test_decode_rejects_short_body() {
+        // too short even for byte-padded rows (18 bytes)
+        let data = build_ilbm_with_body(17, 3, 2, MASK_NONE, COMPRESSION_NONE, &[0; 16]);
+        let err = IffImage::load_from_data(&data).unwrap_err();
+        assert!(err.contains("expected 24"), "{}", err);
+    }
+
+    #[test]
+    fn test_decode_byte_padded_rows() {
+        // 21 pixels: 3 bytes per row byte padded, 4 WORD padded
+        let (width, height) = (21, 5);
+        let body = pattern_body_padded(width, height, false, 3);
+        assert_eq!(body.len(), 3 * 2 * height);
+        let data = build_ilbm_with_body(width, height, 2, MASK_NONE, COMPRESSION_NONE, &body);
+        let image = IffImage::load_from_data(&data).unwrap();
+        assert_eq!(image.pixels.len(), 4 * 2 * height);
+        assert_pattern(&image);
+
+        let data = build_ilbm_with_body(
+            width,
+            height,
+            2,
+            MASK_NONE,
+            COMPRESSION_BYTE_RUN1,
+            &byterun1_literals(&body),
+        );
+        let image = IffImage::load_from_data(&data).unwrap();
+        assert_pattern(&image);
+
+        // the scanline decoder widens rows the same way, compressed or not
+        let row_bytes = 4 * 2;
+        let uncompressed =
+            build_ilbm_with_body(width, height, 2, MASK_NONE, COMPRESSION_NONE, &body);
+        for data in [&data, &uncompressed] {
+            let mut decoder = ScanlineDecoder::new(data).unwrap();
+            for row in 0..height {
+                assert_eq!(
+                    decoder.decode_scanline(row).unwrap(),
+                    image.pixels[row * row_bytes..(row + 1) * row_bytes],
+                    "row {row}"
+                );
+            }
+        }
+
+        // mask planes are byte padded too
+        let body = pattern_body_padded(width, height, true, 3);
+        let data = build_ilbm_with_body(width, height, 2, MASK_HAS_MASK, COMPRESSION_NONE, &body);
+        assert_pattern(&IffImage::load_from_data(&data).unwrap());
+    }
+
+    #[test]
+    fn test_body_stats() {
+        // 32x8, 2 planes: 64 zero bytes packed into a single repeat run
+        let body = [(-63i8) as u8, 0x00];
+        let data = build_ilbm_with_body(32, 8, 2, MASK_NONE, COMPRESSION_BYTE_RUN1, &body);
+        let stats = IffImage::load_from_data(&data).unwrap().body_stats();
+        assert_eq!(
+            stats,
+            BodyStats {
+                compressed: true,
+                disk_bytes: 2,
+                decoded_bytes: 64,
+            }
+        );
+        assert_eq!(stats.ratio(), 32.0);
+
+        let stats = IffImage::load_from_data(&build_ilbm(32, 8, 2))
+            .unwrap()
+            .body_stats();
+        assert!(!stats.compressed);
+        assert_eq!(stats.decoded_bytes, 64);
+        assert_eq!(stats.ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_scanline_decoder_matches_full_decode() {
+        // literal runs of up to 128 bytes cross row boundaries (rows are 30
+        // bytes with the mask plane), followed by a trailing no-op run
+        let width = 80;
+        let body = pattern_body(width, 12, true);
+        let mut encoded = byterun1_literals(&body);
+        encoded.push(0x80);
+        let data =
+            build_ilbm_with_body(width, 12, 2, MASK_HAS_MASK, COMPRESSION_BYTE_RUN1, &encoded);
+        let image = IffImage::load_from_data(&data).unwrap();
+        let row_bytes = image.width.div_ceil(16) * 2 * image.bitplanes;
+
+        let mut decoder = ScanlineDecoder::new(&data).unwrap();
+        assert!(decoder.header().pixels.is_empty());
+        for row in [7, 0, 11, 3] {
+            assert_eq!(
+                decoder.decode_scanline(row).unwrap(),
+                image.pixels[row * row_bytes..(row + 1) * row_bytes],
+                "row {row}"
+            );
+        }
+        assert!(decoder.decode_scanline(12).is_err());
+
+        let body = [(-63i8) as u8, 0xA5];
+        let data = build_ilbm_with_body(32, 8, 2, MASK_NONE, COMPRESSION_BYTE_RUN1, &body);
+        let mut decoder = ScanlineDecoder::new(&data).unwrap();
+        assert_eq!(decoder.decode_scanline(5).unwrap(), vec![0xA5; 8]);
+
+        let data = build_ilbm(20, 3, 2);
+        let mut decoder = ScanlineDecoder::new(&data).unwrap();
+        assert_eq!(decoder.decode_scanline(2).unwrap(), vec![0; 8]);
+    }
+
+    #[test]
+    fn test_assumed_transparent_index() {
+        let opaque = build_ilbm(16, 2, 2);
+        let image = IffImage::load_from_data_opts(&opaque, Some(0)).unwrap();
+        assert_eq!(image.transparent_color, Some(0));
+        let image = IffImage::load_from_data_opts(&opaque, None).unwrap();
+        assert_eq!(image.transparent_color, None);
+
+        // a declared transparent color (5 here) is kept
+        let body = vec![0u8; 2 * 2 * 2];
+        let mut keyed =
+            build_ilbm_with_body(16, 2, 2, MASK_HAS_TRANSPARENCY, COMPRESSION_NONE, &body);
+        // FORM + ILBM + BMHD chunk header, then 12 bytes into the BMHD
+        keyed[32..34].copy_from_slice(&5u16.to_be_bytes());
+        let image = IffImage::load_from_data_opts(&keyed, Some(3)).unwrap();
+        assert_eq!(image.transparent_color, Some(5));
+    }
+
+    #[test]
+    fn test_load_into_reuses_buffer() {
+        let masked = build_ilbm_with_body(
+            40,
+            6,
+            2,
+            MASK_HAS_MASK,
+            COMPRESSION_BYTE_RUN1,
+            &byterun1_literals(&pattern_body(40, 6, true)),
+        );
+        let plain = build_ilbm_with_body(
+            17,
+            3,
+            2,
+            MASK_NONE,
+            COMPRESSION_NONE,
+            &pattern_body(17, 3, false),
+        );
+
+        let mut scratch: Vec<u8> = vec![0xEE; 4];
+        let meta = IffImage::load_into(&masked, &mut scratch).unwrap();
+        assert_eq!((meta.width, meta.height, meta.bitplanes), (40, 6, 2));
+        assert_eq!(scratch.len(), 6 * 2 * 6);
+        assert_eq!(scratch, IffImage::load_from_data(&masked).unwrap().pixels);
+        let capacity = scratch.capacity();
+
+        let meta = IffImage::load_into(&plain, &mut scratch).unwrap();
+        assert_eq!((meta.width, meta.height), (17, 3));
+        assert_eq!(scratch.len(), 4 * 2 * 3);
+        assert_eq!(scratch.capacity(), capacity);
+        let image = IffImage::from_parts(meta, scratch.clone());
+        assert_pattern(&image);
+        assert_eq!(scratch, IffImage::load_from_data(&plain).unwrap().pixels);
+    }
+
+    #[test]
+    fn test_oversized_bmhd_rejected() {
+        let data = build_ilbm_with_body(60000, 60000, 5, MASK_NONE, COMPRESSION_BYTE_RUN1, &[]);
+        let err = IffImage::load_from_data(&data).unwrap_err();
+        assert!(err.contains("60000x60000"), "{}", err);
+
+        let data = build_ilbm(320, 200, 2);
+        assert!(IffImage::load_from_data(&data).is_ok());
+        let limits = ImageLimits {
+            max_dimension: 256,
+            ..ImageLimits::default()
+        };
+        assert!(IffImage::load_from_data_with_limits(&data, &limits).is_err());
+
+        // a BMHD cut off before the plane count is an error, not a panic
+        let mut truncated = Vec::new();
+        let mut contents = b"ILBM".to_vec();
+        push_chunk(&mut contents, b"BMHD", &[0, 16, 0, 16, 0, 0, 0, 0]);
+        push_chunk(&mut truncated, b"FORM", &contents);
+        assert!(IffImage::load_from_data_with_limits(&truncated[..], &limits).is_err());
+    }
+
+    #[test]
+    fn test_load_from_data_at_odd_offset() {
+        let form = build_ilbm_with_body(
+            20,
+            3,
+            2,
+            MASK_NONE,
+            COMPRESSION_NONE,
+            &pattern_body(20, 3, false),
+        );
+        let mut data = vec![0xA5u8; 7];
+        data.extend_from_slice(&form);
+        data.extend_from_slice(b"JUNKJUNK"); // trailing archive data is not parsed
+
+        let image = IffImage::load_from_data_at(&data, 7).unwrap();
+        assert_eq!((image.width, image.height, image.bitplanes), (20, 3, 2));
+        assert_pattern(&image);
+
+        assert!(IffImage::load_from_data_at(&data, 6).is_err());
+        assert!(IffImage::load_from_data_at(&data, data.len()).is_err());
+    }
+
+    #[test]
+    fn test_missing_body_is_an_error() {
+        let mut bmhd = Vec::new();
+        bmhd.extend_from_slice(&[0, 16, 0, 2, 0, 0, 0, 0]); // 16x2 at 0,0
+        bmhd.extend_from_slice(&[2, MASK_NONE, COMPRESSION_NONE, 0, 0, 0, 10, 11, 0, 16, 0, 2]);
+        let mut contents = b"ILBM".to_vec();
+        push_chunk(&mut contents, b"BMHD", &bmhd);
+        let mut data = Vec::new();
+        push_chunk(&mut data, b"FORM", &contents);
+
+        let err = IffImage::load_from_data(&data).unwrap_err();
+        assert!(err.contains("no BODY"), "{}", err);
+        assert!(ScanlineDecoder::new(&data).is_err());
+    }
+
+    #[test]
+    fn test_load_all_from_cat() {
+        let mut contents = b"ILBM".to_vec();
+        contents.extend_from_slice(&build_ilbm(16, 4, 1));
+        contents.extend_from_slice(&build_ilbm(32, 8, 2));
+        let mut cat = Vec::new();
+        push_chunk(&mut cat, b"CAT ", &contents);
+
+        let images = IffImage::load_all_from_data(&cat).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!(
+            (images[0].width, images[0].height, images[0].bitplanes),
+            (16, 4, 1)
+        );
+        assert_eq!(
+            (images[1].width, images[1].height, images[1].bitplanes),
+            (32, 8, 2)
+        );
+        assert_eq!(images[1].pixels.len(), 4 * 2 * 8);
+    }
+
+    #[test]
+    fn test_load_all_from_single_ilbm() {
+        let images = IffImage::load_all_from_data(&build_ilbm(16, 4, 1)).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!((images[0].width, images[0].height), (16, 4));
+    }
+
+    #[test]
+    fn test_thumbnail_preserves_aspect() {
+        let image = striped_image(64, 32);
+        let (pixels, width, height) = image.thumbnail(16, &test_palette());
+        assert_eq!((width, height), (16, 8));
+        assert_eq!(pixels.len(), 16 * 8 * 4);
+
+        // every 4th thumbnail column samples a new 8-pixel stripe
+        assert_eq!(&pixels[0..4], &[0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(&pixels[8..12], &[0x00, 0x00, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn test_thumbnail_does_not_enlarge() {
+        let image = striped_image(16, 4);
+        let (_, width, height) = image.thumbnail(64, &test_palette());
+        assert_eq!((width, height), (16, 4));
+    }
+
+    #[test]
+    fn test_missing_pixels_give_empty_thumbnail() {
+        let mut image = striped_image(16, 4);
+        image.pixels.clear();
+        assert!(image.to_bitmap().is_err());
+        assert_eq!(image.thumbnail(8, &test_palette()), (Vec::new(), 0, 0));
     }
 }
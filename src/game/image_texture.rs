@@ -1,12 +1,34 @@
 use crate::game::bitmap::BitMap;
 use crate::game::colors::Palette;
-use crate::game::iff_image::IffImage;
+use crate::game::game_library::GameLibrary;
+use crate::game::iff_image::{IffImage, ImageKind};
 
+use sdl3::pixels::Color;
 use sdl3::rect::Rect;
 use sdl3::render::{Canvas, RenderTarget, Texture};
 
 use std::cell::RefCell;
-use std::rc::Weak;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+// Placeholder checkerboard: cell size in pixels and the two RGBA colors.
+const PLACEHOLDER_CELL: usize = 8;
+const PLACEHOLDER_COLORS: [[u8; 4]; 2] = [[0xFF, 0x00, 0xFF, 0xFF], [0x00, 0x00, 0x00, 0xFF]];
+
+/// Build a magenta/black RGBA32 checkerboard used in place of missing art.
+///
+/// @return tuple of (pixels, byte stride)
+fn placeholder_pixels(width: usize, height: usize) -> (Vec<u8>, usize) {
+    let stride = width * 4;
+    let mut pixels: Vec<u8> = Vec::with_capacity(stride * height);
+    for yy in 0..height {
+        for xx in 0..width {
+            let cell = (xx / PLACEHOLDER_CELL + yy / PLACEHOLDER_CELL) & 1;
+            pixels.extend_from_slice(&PLACEHOLDER_COLORS[cell]);
+        }
+    }
+    (pixels, stride)
+}
 
 /// An image view inside a shared SDL3 texture atlas.
 ///
@@ -28,42 +50,103 @@ pub struct ImageTexture<'tex> {
 
     // Weak reference to the shared backing texture (owned by the atlas).
     texture: Weak<RefCell<Texture<'tex>>>,
+
+    // Stand-in for an image that failed to load; draws a checkerboard.
+    placeholder: bool,
+
+    // Palette index drawn transparent by `refresh`, from the image's BMHD.
+    key_color: Option<usize>,
 }
 
 impl<'tex> ImageTexture<'tex> {
     /// Build an `ImageTexture` from an `IffImage`.
     ///
     /// The planar pixel data is decoded into a [`BitMap`] immediately;
-    /// after this call the `image` reference is no longer needed.
+    /// after this call the `image` reference is no longer needed. Only
+    /// [`ImageKind::Indexed`] and [`ImageKind::Ehb`] images can be drawn so
+    /// far; other kinds get a placeholder.
     pub fn new(
         image: &IffImage,
         bounds: &Rect,
         texture: Weak<RefCell<Texture<'tex>>>,
     ) -> ImageTexture<'tex> {
-        let row_bytes = ((image.width + 15) / 16) * 2;
-        let bitmap = BitMap::with_interleaved_data(
-            image.pixels.clone(),
-            image.width,
-            image.height,
-            image.bitplanes,
-            row_bytes,
-        );
+        if !matches!(image.kind, ImageKind::Indexed | ImageKind::Ehb) {
+            println!(
+                "Warning: {:?} images are not supported, using a placeholder",
+                image.kind
+            );
+            return ImageTexture::placeholder(bounds, texture);
+        }
+        let bitmap = match image.to_bitmap() {
+            Ok(bitmap) => bitmap,
+            Err(e) => {
+                println!("Warning: {}, using a placeholder", e);
+                return ImageTexture::placeholder(bounds, texture);
+            }
+        };
         ImageTexture {
             bitmap,
             texture_bounds: *bounds,
             pixels_32: Vec::new(),
             stride: 0,
             texture,
+            placeholder: false,
+            key_color: image.transparent_color,
         }
     }
 
+    /// Build a placeholder `ImageTexture` for an asset that is missing or
+    /// failed to decode. It fills `bounds` with a magenta checkerboard so the
+    /// gap is obvious on screen without being fatal.
+    pub fn placeholder(bounds: &Rect, texture: Weak<RefCell<Texture<'tex>>>) -> ImageTexture<'tex> {
+        // the bitmap only carries the size; its planes are never drawn
+        let bitmap = BitMap::build(bounds.width() as usize, bounds.height() as usize, 1)
+            .unwrap_or_else(|_| BitMap::new());
+        ImageTexture {
+            bitmap,
+            texture_bounds: *bounds,
+            pixels_32: Vec::new(),
+            stride: 0,
+            texture,
+            placeholder: true,
+            key_color: None,
+        }
+    }
+
+    pub fn is_placeholder(&self) -> bool {
+        self.placeholder
+    }
+
     pub fn get_bounds(&self) -> &Rect {
         &self.texture_bounds
     }
 
+    /// Key color used by `refresh`: the source image's declared transparent
+    /// color, if any.
+    pub fn key_color(&self) -> Option<usize> {
+        self.key_color
+    }
+
+    /// Rebuild and upload the texture with the image's own transparency.
+    /// Same as `update(palette, self.key_color())`.
+    pub fn refresh(&mut self, palette: &Palette) {
+        self.update(palette, self.key_color);
+    }
+
+    /// Rebuild and upload the texture. `key_color` overrides the image's own
+    /// transparent color: pass `None` to draw every index opaque, or `Some`
+    /// to key a different index. Use `refresh` for the image's default.
     pub fn update(&mut self, palette: &Palette, key_color: Option<usize>) {
-        // build the pixel cache if needed
-        if self.pixels_32.is_empty() {
+        if self.placeholder {
+            // palette independent, so only build once
+            if self.pixels_32.is_empty() {
+                let (width, height) = self.bitmap.get_size();
+                let (pixels, stride) = placeholder_pixels(width, height);
+                self.pixels_32 = pixels;
+                self.stride = stride;
+            }
+        } else if self.pixels_32.is_empty() {
+            // build the pixel cache
             let result = self.bitmap.generate_rgb32(palette, key_color);
             if result.is_err() {
                 println!(
@@ -113,6 +196,24 @@ impl<'tex> ImageTexture<'tex> {
         }
     }
 
+    /// Draw the image tinted by `tint` (multiplied into each pixel's RGB).
+    /// The atlas texture is shared, so its previous color mod is restored
+    /// afterwards and other images are unaffected.
+    pub fn draw_tinted<T: RenderTarget>(&self, canvas: &mut Canvas<T>, x: i32, y: i32, tint: Color) {
+        if let Some(strong_texture) = self.texture.upgrade() {
+            let saved_mod = strong_texture.borrow().color_mod();
+            strong_texture
+                .borrow_mut()
+                .set_color_mod(tint.r, tint.g, tint.b);
+            self.draw(canvas, x, y);
+            strong_texture
+                .borrow_mut()
+                .set_color_mod(saved_mod.0, saved_mod.1, saved_mod.2);
+        } else {
+            println!("Error upgrading weak reference to shared texture in ImageTexture::draw_tinted");
+        }
+    }
+
     /// Draw the image scaled to fill `dst` (no aspect-ratio enforcement).
     pub fn draw_scaled<T: RenderTarget>(&self, canvas: &mut Canvas<T>, dst: Rect) {
         if let Some(strong_texture) = self.texture.upgrade() {
@@ -155,3 +256,412 @@ impl<'tex> ImageTexture<'tex> {
         }
     }
 }
+
+/// Packs several [`BitMap`]s into one region of a shared atlas texture.
+///
+/// Sprites are laid out left to right in rows within `bounds`, converted to
+/// RGBA32 with a common palette, and uploaded with a single `texture.update`
+/// instead of one upload per sprite.
+pub struct AtlasBuilder {
+    bounds: Rect,
+    sprites: Vec<BitMap>,
+}
+
+impl AtlasBuilder {
+    pub fn new(bounds: &Rect) -> AtlasBuilder {
+        AtlasBuilder {
+            bounds: *bounds,
+            sprites: Vec::new(),
+        }
+    }
+
+    /// Queue a sprite for packing, returning its index in the result rects.
+    pub fn add(&mut self, bitmap: BitMap) -> usize {
+        self.sprites.push(bitmap);
+        self.sprites.len() - 1
+    }
+
+    /// Compute the atlas source rect of each queued sprite.
+    ///
+    /// Uses the same row packing as the main image atlas: advance right until
+    /// a sprite no longer fits, then start a new row below the tallest sprite
+    /// in the current row.
+    pub fn layout(&self) -> Result<Vec<Rect>, String> {
+        let atlas_w = self.bounds.width() as usize;
+        let atlas_h = self.bounds.height() as usize;
+        let mut rects: Vec<Rect> = Vec::with_capacity(self.sprites.len());
+        let mut next_x = 0;
+        let mut next_y = 0;
+        let mut row_h = 0;
+
+        for (index, sprite) in self.sprites.iter().enumerate() {
+            let (width, height) = sprite.get_size();
+            if width > atlas_w {
+                return Err(format!(
+                    "Sprite {index} is wider ({width}) than the atlas region ({atlas_w})"
+                ));
+            }
+
+            // Advance to next row if this sprite does not fit horizontally.
+            if next_x + width > atlas_w {
+                next_x = 0;
+                next_y += row_h;
+                row_h = 0;
+            }
+            if next_y + height > atlas_h {
+                return Err(format!("Sprite {index} does not fit in the atlas region"));
+            }
+
+            rects.push(Rect::new(
+                self.bounds.x() + next_x as i32,
+                self.bounds.y() + next_y as i32,
+                width as u32,
+                height as u32,
+            ));
+            next_x += width;
+            row_h = row_h.max(height);
+        }
+
+        Ok(rects)
+    }
+
+    /// Build the combined RGBA32 pixel buffer covering the whole atlas region.
+    ///
+    /// @return tuple of (pixels, byte stride, per-sprite atlas rects)
+    pub fn build_pixels(
+        &self,
+        palette: &Palette,
+        key_color: Option<usize>,
+    ) -> Result<(Vec<u8>, usize, Vec<Rect>), String> {
+        let rects = self.layout()?;
+        let stride = self.bounds.width() as usize * 4;
+        let mut pixels: Vec<u8> = vec![0; stride * self.bounds.height() as usize];
+
+        for (sprite, rect) in self.sprites.iter().zip(rects.iter()) {
+            let (sprite_pixels, sprite_stride) = sprite.generate_rgb32(palette, key_color)?;
+            let local_x = (rect.x() - self.bounds.x()) as usize;
+            let local_y = (rect.y() - self.bounds.y()) as usize;
+            for row in 0..rect.height() as usize {
+                let src = row * sprite_stride;
+                let dst = (local_y + row) * stride + local_x * 4;
+                pixels[dst..dst + sprite_stride]
+                    .copy_from_slice(&sprite_pixels[src..src + sprite_stride]);
+            }
+        }
+
+        Ok((pixels, stride, rects))
+    }
+
+    /// Convert every queued sprite and upload them to the shared texture in
+    /// one call, returning each sprite's source rect within the atlas.
+    pub fn upload(
+        &self,
+        palette: &Palette,
+        key_color: Option<usize>,
+        texture: &Weak<RefCell<Texture<'_>>>,
+    ) -> Result<Vec<Rect>, String> {
+        let (pixels, stride, rects) = self.build_pixels(palette, key_color)?;
+
+        let strong_texture = texture
+            .upgrade()
+            .ok_or("Error upgrading weak reference to shared texture in AtlasBuilder")?;
+        strong_texture
+            .borrow_mut()
+            .update(Some(self.bounds), &pixels, stride)
+            .map_err(|e| e.to_string())?;
+
+        Ok(rects)
+    }
+}
+
+// Cached textures of one image, each with the palette colors it was drawn with.
+type PaletteTextures<'tex> = Vec<(Vec<u16>, Rc<ImageTexture<'tex>>)>;
+
+/// Library images uploaded on demand into a region of a shared atlas
+/// texture, cached by image name and palette so repeated draws (e.g. a
+/// placard background each frame) reuse the uploaded texture.
+///
+/// Palettes are identified by their colors, so any palette with the same
+/// entries shares a cache entry. Atlas space is handed out in rows like
+/// [`AtlasBuilder`] and is not reclaimed when entries are invalidated.
+pub struct ImageTextureCache<'tex> {
+    bounds: Rect,
+    texture: Weak<RefCell<Texture<'tex>>>,
+    entries: HashMap<String, PaletteTextures<'tex>>,
+
+    // next free atlas position, relative to `bounds`
+    next_x: u32,
+    next_y: u32,
+    row_h: u32,
+}
+
+impl<'tex> ImageTextureCache<'tex> {
+    pub fn new(bounds: &Rect, texture: Weak<RefCell<Texture<'tex>>>) -> ImageTextureCache<'tex> {
+        ImageTextureCache {
+            bounds: *bounds,
+            texture,
+            entries: HashMap::new(),
+            next_x: 0,
+            next_y: 0,
+            row_h: 0,
+        }
+    }
+
+    /// Texture for library image `name` drawn with `palette`, uploading it
+    /// on the first request for that name and palette.
+    pub fn get_image_texture(
+        &mut self,
+        game_lib: &GameLibrary,
+        name: &str,
+        palette: &Palette,
+    ) -> Result<Rc<ImageTexture<'tex>>, String> {
+        let cached = self.entries.get(name).and_then(|textures| {
+            textures.iter().find(|(colors, _)| {
+                colors
+                    .iter()
+                    .copied()
+                    .eq(palette.colors.iter().map(|c| c.color))
+            })
+        });
+        if let Some((_, cached)) = cached {
+            return Ok(Rc::clone(cached));
+        }
+
+        let image = game_lib
+            .find_image(name)
+            .and_then(|asset| asset.image.as_ref())
+            .ok_or_else(|| format!("No image data for {}", name))?;
+        let slot = self.allocate(image.width as u32, image.height as u32)?;
+        let mut image_texture = ImageTexture::new(image, &slot, self.texture.clone());
+        image_texture.refresh(palette);
+
+        let image_texture = Rc::new(image_texture);
+        self.entries
+            .entry(name.to_string())
+            .or_default()
+            .push((palette.to_copper_words(), Rc::clone(&image_texture)));
+        Ok(image_texture)
+    }
+
+    /// Drop every cached texture of image `name`, e.g. after the asset was
+    /// reloaded from disk. The next request uploads it again.
+    pub fn invalidate(&mut self, name: &str) {
+        self.entries.remove(name);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Reserve a `width` x `height` atlas rect, starting a new row when the
+    // current one is full. A failed allocation leaves the cursor where it was.
+    fn allocate(&mut self, width: u32, height: u32) -> Result<Rect, String> {
+        let (mut x, mut y, mut row_h) = (self.next_x, self.next_y, self.row_h);
+        if x + width > self.bounds.width() {
+            x = 0;
+            y += row_h;
+            row_h = 0;
+        }
+        if width > self.bounds.width() || y + height > self.bounds.height() {
+            return Err(format!(
+                "{}x{} image does not fit in the texture cache",
+                width, height
+            ));
+        }
+        let slot = Rect::new(
+            self.bounds.x() + x as i32,
+            self.bounds.y() + y as i32,
+            width,
+            height,
+        );
+        self.next_x = x + width;
+        self.next_y = y;
+        self.row_h = row_h.max(height);
+        Ok(slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::colors::RGB4;
+
+    fn test_palette() -> Palette {
+        Palette {
+            colors: vec![
+                RGB4::from(0x000),
+                RGB4::from(0xFFF),
+                RGB4::from(0xE00),
+                RGB4::from(0x390),
+            ],
+        }
+    }
+
+    // Solid sprite where every pixel uses `color` (2 bitplanes).
+    fn solid_sprite(width: usize, height: usize, color: usize) -> BitMap {
+        let mut bitmap = BitMap::build(width, height, 2).unwrap();
+        for (pp, plane) in bitmap.planes.iter_mut().enumerate() {
+            if color & (1 << pp) != 0 {
+                plane.fill(0xFF);
+            }
+        }
+        bitmap
+    }
+
+    #[test]
+    fn test_texture_cache_reuses_until_palette_changes() {
+        let game_lib =
+            crate::game::game_library::load_game_library(std::path::Path::new("faery.toml"))
+                .unwrap();
+        let palette = Palette::ramp(RGB4::from(0x000), RGB4::from(0xFFF), 32);
+        let mut cache = ImageTextureCache::new(&Rect::new(0, 0, 1024, 1024), Weak::new());
+
+        let first = cache.get_image_texture(&game_lib, "page0", &palette).unwrap();
+        let second = cache.get_image_texture(&game_lib, "page0", &palette).unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(cache.len(), 1);
+
+        let mut other_palette = palette.clone();
+        other_palette.colors[1] = RGB4::from(0x888);
+        let recolored = cache.get_image_texture(&game_lib, "page0", &other_palette).unwrap();
+        assert!(!Rc::ptr_eq(&first, &recolored));
+        assert_ne!(first.get_bounds(), recolored.get_bounds());
+        assert_eq!(cache.len(), 2);
+
+        cache.invalidate("page0");
+        assert!(cache.is_empty());
+        let reloaded = cache.get_image_texture(&game_lib, "page0", &palette).unwrap();
+        assert!(!Rc::ptr_eq(&first, &reloaded));
+
+        assert!(cache.get_image_texture(&game_lib, "nope", &palette).is_err());
+    }
+
+    #[test]
+    fn test_texture_cache_failed_allocation_keeps_cursor() {
+        let mut cache = ImageTextureCache::new(&Rect::new(0, 0, 32, 16), Weak::new());
+        assert_eq!(cache.allocate(24, 8).unwrap(), Rect::new(0, 0, 24, 8));
+
+        // too tall for the next row; the current row must stay open
+        assert!(cache.allocate(16, 16).is_err());
+        assert_eq!(cache.allocate(8, 8).unwrap(), Rect::new(24, 0, 8, 8));
+        assert_eq!(cache.allocate(16, 8).unwrap(), Rect::new(0, 8, 16, 8));
+    }
+
+    #[test]
+    fn test_draw_tinted_restores_color_mod() {
+        let surface = sdl3::surface::Surface::new(32, 32, sdl3::pixels::PixelFormat::RGBA32).unwrap();
+        let mut canvas = surface.into_canvas().unwrap();
+        let creator = canvas.texture_creator();
+        let atlas = creator
+            .create_texture_static(Some(sdl3::pixels::PixelFormat::RGBA32), 32, 32)
+            .unwrap();
+        let backing = Rc::new(RefCell::new(atlas));
+        backing.borrow_mut().set_color_mod(10, 20, 30);
+
+        let mut image = ImageTexture::placeholder(&Rect::new(0, 0, 16, 16), Rc::downgrade(&backing));
+        image.refresh(&test_palette());
+        image.draw_tinted(&mut canvas, 4, 4, Color::RGB(255, 0, 0));
+
+        assert_eq!(backing.borrow().color_mod(), (10, 20, 30));
+    }
+
+    #[test]
+    fn test_atlas_builder_rects_within_bounds_and_disjoint() {
+        let bounds = Rect::new(16, 8, 40, 40);
+        let mut builder = AtlasBuilder::new(&bounds);
+        builder.add(solid_sprite(16, 8, 1));
+        builder.add(solid_sprite(16, 16, 2));
+        builder.add(solid_sprite(16, 4, 3));
+        builder.add(solid_sprite(32, 8, 1));
+
+        let (pixels, stride, rects) = builder.build_pixels(&test_palette(), None).unwrap();
+        assert_eq!(rects.len(), 4);
+
+        for (i, a) in rects.iter().enumerate() {
+            assert!(bounds.contains_rect(*a));
+            for b in rects.iter().skip(i + 1) {
+                let disjoint = a.right() <= b.left()
+                    || b.right() <= a.left()
+                    || a.bottom() <= b.top()
+                    || b.bottom() <= a.top();
+                assert!(disjoint, "{:?} overlaps {:?}", a, b);
+            }
+        }
+
+        // every sprite's pixels are present at its rect in the combined buffer
+        for (sprite, rect) in builder.sprites.iter().zip(rects.iter()) {
+            let (sprite_pixels, sprite_stride) =
+                sprite.generate_rgb32(&test_palette(), None).unwrap();
+            let local_x = (rect.x() - bounds.x()) as usize;
+            let local_y = (rect.y() - bounds.y()) as usize;
+            for row in 0..rect.height() as usize {
+                let dst = (local_y + row) * stride + local_x * 4;
+                assert_eq!(
+                    &pixels[dst..dst + sprite_stride],
+                    &sprite_pixels[row * sprite_stride..(row + 1) * sprite_stride]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_refresh_uses_declared_transparent_color() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("game/hiscreen");
+        let image = IffImage::load_from_file(&path).expect("game/hiscreen must be readable");
+        let transparent = image.transparent_color.expect("hiscreen declares a transparent color");
+        let bounds = Rect::new(0, 0, image.width as u32, image.height as u32);
+
+        let mut texture = ImageTexture::new(&image, &bounds, Weak::new());
+        assert_eq!(texture.key_color(), Some(transparent));
+        texture.refresh(image.colormap.as_ref().unwrap());
+
+        let mut keyed = 0;
+        for (xx, yy, index) in texture.bitmap.indices() {
+            let alpha = texture.pixels_32[yy * texture.stride + xx * 4 + 3];
+            assert_eq!(alpha == 0, index == transparent, "pixel ({xx}, {yy})");
+            keyed += (index == transparent) as usize;
+        }
+        assert!(keyed > 0);
+
+        // an explicit None overrides the default
+        texture.update(image.colormap.as_ref().unwrap(), None);
+        assert!(texture.pixels_32.chunks_exact(4).all(|px| px[3] == 0xFF));
+    }
+
+    #[test]
+    fn test_placeholder_checkerboard() {
+        let texture = ImageTexture::placeholder(&Rect::new(0, 0, 24, 16), Weak::new());
+        assert!(texture.is_placeholder());
+        assert_eq!(texture.bitmap.get_size(), (24, 16));
+
+        let (pixels, stride) = placeholder_pixels(24, 16);
+        assert_eq!(stride, 24 * 4);
+        assert_eq!(pixels.len(), stride * 16);
+
+        let pixel = |x: usize, y: usize| &pixels[y * stride + x * 4..y * stride + x * 4 + 4];
+        let magenta = &PLACEHOLDER_COLORS[0][..];
+        let black = &PLACEHOLDER_COLORS[1][..];
+        assert_eq!(pixel(0, 0), magenta);
+        assert_eq!(pixel(7, 7), magenta);
+        assert_eq!(pixel(8, 0), black);
+        assert_eq!(pixel(0, 8), black);
+        assert_eq!(pixel(8, 8), magenta);
+        assert_eq!(pixel(23, 15), black);
+    }
+
+    #[test]
+    fn test_atlas_builder_overflow() {
+        let mut builder = AtlasBuilder::new(&Rect::new(0, 0, 16, 16));
+        builder.add(solid_sprite(16, 16, 1));
+        builder.add(solid_sprite(16, 1, 1));
+        assert!(builder.layout().is_err());
+
+        let mut builder = AtlasBuilder::new(&Rect::new(0, 0, 16, 16));
+        builder.add(solid_sprite(32, 1, 1));
+        assert!(builder.layout().is_err());
+    }
+}
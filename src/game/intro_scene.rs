@@ -8,6 +8,7 @@ use sdl3::rect::Rect;
 use sdl3::render::{Canvas, Texture};
 use sdl3::video::Window;
 
+use crate::game::colors::Palette;
 use crate::game::game_library::GameLibrary;
 use crate::game::page_flip::PageFlip;
 use crate::game::palette_fader::{FadeController, FadeResult};
@@ -300,8 +301,11 @@ impl Scene for IntroScene {
                     play_canvas.set_draw_color(Color::BLACK);
                     play_canvas.clear();
                     resources.topaz_font.set_color_mod(255, 255, 255);
+                    let fallback_palette = Palette::default();
+                    let textcolors_palette =
+                        game_lib.find_palette("textcolors").unwrap_or(&fallback_palette);
                     if let Some(placard) = game_lib.find_placard("titletext") {
-                        placard.draw(resources.topaz_font, play_canvas);
+                        placard.draw(resources.topaz_font, play_canvas, textcolors_palette);
                     }
                 });
 
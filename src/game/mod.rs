@@ -9,11 +9,13 @@ pub mod bitblit;
 pub mod bitmap;
 pub mod byteops;
 pub mod collision;
+pub mod color_cycle;
 pub mod colors;
 pub mod combat;
 pub mod copper;
 pub mod copy_protect_scene;
 pub mod cursor;
+pub mod day_night;
 pub mod day_phase;
 pub mod debug_command;
 pub mod debug_items;
@@ -30,6 +32,7 @@ pub mod game_event;
 pub mod game_library;
 pub mod game_state;
 pub mod gfx_effects;
+pub mod gif;
 pub mod hiscreen;
 pub mod hunk;
 pub mod iff_image;
@@ -54,6 +57,8 @@ pub mod placard_scene;
 pub mod render_resources;
 pub mod render_task;
 pub mod scene;
+pub mod screenshot;
+pub mod scroll_text;
 pub mod settings;
 pub mod shop;
 pub mod songs;
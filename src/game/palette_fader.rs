@@ -371,21 +371,7 @@ impl PaletteFader {
 
 /// Linearly interpolate between two RGB4 colors at parameter t (0.0 = from, 1.0 = to).
 fn lerp_rgb4(from: &RGB4, to: &RGB4, t: f32) -> RGB4 {
-    let fr = ((from.color & 0xF00) >> 8) as f32;
-    let fg = ((from.color & 0x0F0) >> 4) as f32;
-    let fb = (from.color & 0x00F) as f32;
-
-    let tr = ((to.color & 0xF00) >> 8) as f32;
-    let tg = ((to.color & 0x0F0) >> 4) as f32;
-    let tb = (to.color & 0x00F) as f32;
-
-    let r = (fr + (tr - fr) * t).round() as u16;
-    let g = (fg + (tg - fg) * t).round() as u16;
-    let b = (fb + (tb - fb) * t).round() as u16;
-
-    RGB4 {
-        color: (r << 8) | (g << 4) | b,
-    }
+    from.blend(to, t)
 }
 
 #[cfg(test)]
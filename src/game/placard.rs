@@ -1,32 +1,90 @@
 use crate::game::colors::Palette;
-use crate::game::font_texture::FontTexture;
+use crate::game::font::DiskFont;
+use crate::game::font_texture::{parse_color_escapes, FontTexture};
 use crate::game::render_task::RenderTask;
 
 use sdl3::pixels::Color;
 use sdl3::rect::Point;
+use sdl3::rect::Rect;
 use sdl3::render::Canvas;
 use sdl3::render::RenderTarget;
 
 use sdl3::video::Window;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /*
  * A page of text, possibly with a fancy swirly border.
  */
 
-#[derive(Deserialize, Debug)]
+// Serialize lets editors write a modified library back out with the same
+// field names it was loaded with.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PlacardLine {
     x: usize,
     y: usize,
     text: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Placard {
     #[serde(default)]
     lines: Vec<PlacardLine>,
 }
 
+// Assumed character cell size used by `Placard::to_text_grid`, matching the
+// 8x8 topaz-style font placards are normally drawn with.
+const TEXT_GRID_CELL_WIDTH: usize = 8;
+const TEXT_GRID_CELL_HEIGHT: usize = 8;
+
+/// How a placard enters the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlacardEntrance {
+    SlideFromLeft,
+    SlideFromRight,
+    SlideFromTop,
+    SlideFromBottom,
+    FadeIn,
+}
+
+/// An entrance animation: slide in from `distance` pixels away, or fade in,
+/// over `duration_ticks` ticks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacardAnimation {
+    pub entrance: PlacardEntrance,
+    pub distance: i32,
+    pub duration_ticks: u32,
+}
+
+impl PlacardAnimation {
+    /// Progress (0..=1) after `ticks` ticks of the animation.
+    pub fn progress(&self, ticks: u32) -> f32 {
+        if self.duration_ticks == 0 {
+            return 1.0;
+        }
+        (ticks as f32 / self.duration_ticks as f32).min(1.0)
+    }
+
+    /// Draw offset at `progress`; shrinks linearly to (0, 0) at 1.
+    pub fn offset(&self, progress: f32) -> (i32, i32) {
+        let remaining = (self.distance as f32 * (1.0 - progress.clamp(0.0, 1.0))).round() as i32;
+        match self.entrance {
+            PlacardEntrance::SlideFromLeft => (-remaining, 0),
+            PlacardEntrance::SlideFromRight => (remaining, 0),
+            PlacardEntrance::SlideFromTop => (0, -remaining),
+            PlacardEntrance::SlideFromBottom => (0, remaining),
+            PlacardEntrance::FadeIn => (0, 0),
+        }
+    }
+
+    /// Text alpha at `progress`; only fades change it.
+    pub fn alpha(&self, progress: f32) -> u8 {
+        match self.entrance {
+            PlacardEntrance::FadeIn => (255.0 * progress.clamp(0.0, 1.0)).round() as u8,
+            _ => 255,
+        }
+    }
+}
+
 /// Parse an ssp-encoded byte stream into placard lines.
 /// The format uses bytes `128 + x_half` followed by `y`, then ASCII text
 /// until the next escape or a 0 terminator.
@@ -67,6 +125,14 @@ fn parse_ssp_lines(data: &[u8]) -> Vec<PlacardLine> {
     lines
 }
 
+// Line text as drawn, with color escapes removed.
+fn visible_text(text: &str) -> String {
+    parse_color_escapes(text)
+        .into_iter()
+        .map(|run| run.text)
+        .collect()
+}
+
 impl Placard {
     pub fn from_ssp_bytes(data: &[u8]) -> Placard {
         Placard {
@@ -81,6 +147,31 @@ impl Placard {
         }
     }
 
+    /// Lay the placard out on a `cols` x `rows` character grid, for snapshot
+    /// tests of placard layout without SDL. Each line starts at the cell
+    /// containing its `x`/`y` position; later lines overwrite earlier ones and
+    /// anything outside the grid is dropped.
+    pub fn to_text_grid(&self, cols: usize, rows: usize) -> Vec<String> {
+        let mut grid: Vec<Vec<char>> = vec![vec![' '; cols]; rows];
+        for line in &self.lines {
+            let row = line.y / TEXT_GRID_CELL_HEIGHT;
+            if row >= rows {
+                continue;
+            }
+            let col = line.x / TEXT_GRID_CELL_WIDTH;
+            for (cell, ch) in grid[row]
+                .iter_mut()
+                .skip(col)
+                .zip(visible_text(&line.text).chars())
+            {
+                *cell = ch;
+            }
+        }
+        grid.into_iter()
+            .map(|row| row.into_iter().collect())
+            .collect()
+    }
+
     pub fn text_lines_with_substitution(&self, substitution: Option<&str>) -> Vec<String> {
         self.lines
             .iter()
@@ -91,32 +182,82 @@ impl Placard {
             .collect()
     }
 
-    pub fn draw<'a, T: RenderTarget>(&self, font: &FontTexture<'a>, canvas: &mut Canvas<T>) {
+    /// Measure the bounding box enclosing every line of the placard.
+    /// Line positions are baselines (as in `draw`), so each line's rect starts
+    /// `font.baseline` pixels above its `y` and is `font.y_size` tall.
+    /// An empty placard has no bounds and yields `None`.
+    pub fn bounds(&self, font: &DiskFont) -> Option<Rect> {
+        let mut extent: Option<(i32, i32, i32, i32)> = None;
         for line in &self.lines {
-            font.render_string(&line.text, canvas, line.x as i32, line.y as i32);
+            let left = line.x as i32;
+            let top = line.y as i32 - font.baseline as i32;
+            let right = left + font.string_width(&visible_text(&line.text));
+            let bottom = top + font.y_size as i32;
+            extent = Some(match extent {
+                None => (left, top, right, bottom),
+                Some((l, t, r, b)) => (l.min(left), t.min(top), r.max(right), b.max(bottom)),
+            });
         }
+
+        extent.map(|(l, t, r, b)| Rect::new(l, t, (r - l) as u32, (b - t) as u32))
+    }
+
+    /// Draw every line, interpreting `{cN}` / `{/}` color escapes with
+    /// colors from `palette`.
+    pub fn draw<'a, T: RenderTarget>(
+        &self,
+        font: &FontTexture<'a>,
+        canvas: &mut Canvas<T>,
+        palette: &Palette,
+    ) {
+        self.draw_offset(font, canvas, 0, 0, palette);
     }
 
     /// Draw the placard text with a pixel offset applied to all line positions.
     /// Useful for positioning the text within different screen regions (e.g.,
     /// title text centered vertically on a 640x480 canvas).
+    /// Color escapes are interpreted as in `draw`.
     pub fn draw_offset<'a, T: RenderTarget>(
         &self,
         font: &FontTexture<'a>,
         canvas: &mut Canvas<T>,
         x_offset: i32,
         y_offset: i32,
+        palette: &Palette,
     ) {
         for line in &self.lines {
-            font.render_string(
+            font.render_string_escaped(
                 &line.text,
                 canvas,
                 line.x as i32 + x_offset,
                 line.y as i32 + y_offset,
+                palette,
             );
         }
     }
 
+    /// Draw the placard partway through an entrance animation. `progress` is
+    /// clamped to 0..=1; at 1 the placard is drawn in its final position at
+    /// full opacity.
+    pub fn draw_animated<'a, T: RenderTarget>(
+        &self,
+        font: &FontTexture<'a>,
+        canvas: &mut Canvas<T>,
+        animation: &PlacardAnimation,
+        progress: f32,
+        palette: &Palette,
+    ) {
+        let (x_offset, y_offset) = animation.offset(progress);
+        let alpha = animation.alpha(progress);
+        if alpha < 255 {
+            font.set_alpha_mod(alpha);
+        }
+        self.draw_offset(font, canvas, x_offset, y_offset, palette);
+        if alpha < 255 {
+            font.set_alpha_mod(255);
+        }
+    }
+
     /// Like `draw_offset`, but substitutes `%` in every line with the given
     /// name. This mirrors the original `name()` call emitted inline between
     /// `placard_text()` calls — e.g. for the victory and princess-rescue
@@ -129,14 +270,16 @@ impl Placard {
         x_offset: i32,
         y_offset: i32,
         substitution: &str,
+        palette: &Palette,
     ) {
         for line in &self.lines {
             let text = line.text.replace('%', substitution);
-            font.render_string(
+            font.render_string_escaped(
                 &text,
                 canvas,
                 line.x as i32 + x_offset,
                 line.y as i32 + y_offset,
+                palette,
             );
         }
     }
@@ -150,13 +293,15 @@ impl Placard {
         canvas: &mut Canvas<T>,
         x_offset: i32,
         y_offset: i32,
+        palette: &Palette,
     ) {
         for line in &self.lines {
-            font.render_string(
+            font.render_string_escaped(
                 &line.text,
                 canvas,
                 (line.x as i32) * 2 + x_offset,
                 (line.y as i32) * 2 + y_offset,
+                palette,
             );
         }
     }
@@ -404,6 +549,24 @@ pub fn draw_placard_border<'a, T: RenderTarget>(canvas: &mut Canvas<T>, palette:
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_placards_json_round_trip() {
+        // placards are named by their key in the library's placards table
+        let json = r#"{
+            "intro": { "lines": [{ "x": 16, "y": 8, "text": "Hi" }, { "x": 40, "y": 24, "text": "{c3}Bye" }] },
+            "blank": {}
+        }"#;
+        let placards: std::collections::HashMap<String, Placard> =
+            serde_json::from_str(json).unwrap();
+        assert_eq!(placards["intro"].lines.len(), 2);
+        assert!(placards["blank"].lines.is_empty());
+
+        let written = serde_json::to_string(&placards).unwrap();
+        let reloaded: std::collections::HashMap<String, Placard> =
+            serde_json::from_str(&written).unwrap();
+        assert_eq!(reloaded, placards);
+    }
+
     #[test]
     fn test_parse_ssp_xy_escape() {
         let data = [128u8 + 5, 10, b'H', b'i', 0];
@@ -424,4 +587,135 @@ mod tests {
         assert_eq!(lines[1].y, 8);
         assert_eq!(lines[1].text, "C");
     }
+
+    #[test]
+    fn test_text_grid_places_lines() {
+        // "Hi" at (16, 8) -> col 2 row 1; "Bye" at (40, 24) -> col 5 row 3
+        let data = [
+            128u8 + 8,
+            8,
+            b'H',
+            b'i',
+            128u8 + 20,
+            24,
+            b'B',
+            b'y',
+            b'e',
+            0,
+        ];
+        let placard = Placard::from_ssp_bytes(&data);
+        let grid = placard.to_text_grid(10, 4);
+        assert_eq!(
+            grid,
+            vec!["          ", "  Hi      ", "          ", "     Bye  "]
+        );
+    }
+
+    #[test]
+    fn test_text_grid_overwrites_and_clips() {
+        let data = [
+            128u8,
+            0,
+            b'A',
+            b'A',
+            b'A',
+            128u8 + 4,
+            0,
+            b'B',
+            b'B',
+            b'B',
+            b'B',
+            0,
+        ];
+        let placard = Placard::from_ssp_bytes(&data);
+        assert_eq!(placard.to_text_grid(4, 1), vec!["ABBB"]);
+    }
+
+    #[test]
+    fn test_slide_entrance_offsets() {
+        let animation = PlacardAnimation {
+            entrance: PlacardEntrance::SlideFromLeft,
+            distance: 100,
+            duration_ticks: 30,
+        };
+        assert_eq!(animation.offset(0.0), (-100, 0));
+        assert_eq!(animation.offset(0.5), (-50, 0));
+        assert_eq!(animation.offset(1.0), (0, 0));
+        // out of range progress clamps
+        assert_eq!(animation.offset(-1.0), (-100, 0));
+        assert_eq!(animation.offset(2.0), (0, 0));
+        assert_eq!(animation.alpha(0.0), 255);
+
+        assert_eq!(animation.progress(15), 0.5);
+        assert_eq!(animation.progress(45), 1.0);
+    }
+
+    #[test]
+    fn test_fade_entrance_alpha() {
+        let animation = PlacardAnimation {
+            entrance: PlacardEntrance::FadeIn,
+            distance: 0,
+            duration_ticks: 0,
+        };
+        assert_eq!(animation.alpha(0.0), 0);
+        assert_eq!(animation.alpha(1.0), 255);
+        assert_eq!(animation.offset(0.0), (0, 0));
+        assert_eq!(animation.progress(0), 1.0);
+    }
+
+    fn test_font() -> DiskFont {
+        let mut font = DiskFont::new();
+        font.y_size = 8;
+        font.x_size = 8;
+        font.baseline = 6;
+        font.lo_char = 32;
+        font.hi_char = 126;
+        font
+    }
+
+    #[test]
+    fn test_bounds_encloses_all_lines() {
+        let data = [
+            128u8 + 5,
+            20,
+            b'H',
+            b'i',
+            128u8 + 2,
+            40,
+            b'W',
+            b'o',
+            b'r',
+            b'l',
+            b'd',
+            0,
+        ];
+        let placard = Placard::from_ssp_bytes(&data);
+        let font = test_font();
+        let bounds = placard.bounds(&font).unwrap();
+
+        for line in &placard.lines {
+            let line_rect = Rect::new(
+                line.x as i32,
+                line.y as i32 - font.baseline as i32,
+                font.string_width(&line.text) as u32,
+                font.y_size as u32,
+            );
+            assert!(bounds.contains_rect(line_rect));
+        }
+        assert_eq!(bounds.x(), 4);
+        assert_eq!(bounds.y(), 14);
+        assert_eq!(bounds.width(), 40);
+        assert_eq!(bounds.height(), 28);
+    }
+
+    #[test]
+    fn test_bounds_empty_placard() {
+        let placard = Placard::from_ssp_bytes(&[0]);
+        assert!(placard.bounds(&test_font()).is_none());
+
+        // a single character is exactly one glyph cell
+        let placard = Placard::from_ssp_bytes(&[128, 6, b'A', 0]);
+        let bounds = placard.bounds(&test_font()).unwrap();
+        assert_eq!((bounds.width(), bounds.height()), (8, 8));
+    }
 }
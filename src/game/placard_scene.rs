@@ -7,6 +7,7 @@ use sdl3::rect::Rect;
 use sdl3::render::{Canvas, Texture};
 use sdl3::video::Window;
 
+use crate::game::colors::Palette;
 use crate::game::game_library::GameLibrary;
 use crate::game::palette_fader::{FadeController, FadeResult};
 use crate::game::placard::{start_placard_renderer, PlacardRenderer};
@@ -131,6 +132,8 @@ impl Scene for PlacardScene {
 
                 // Draw text to play_tex (border will be animated separately)
                 let placard_name = self.placard_name.clone();
+                let fallback_palette = Palette::default();
+                let text_palette = palette.unwrap_or(&fallback_palette);
                 let substitution = self.substitution.clone();
                 // Set the font color to palette index 24 (red in pagecolors).
                 if let Some(pal) = palette {
@@ -153,12 +156,14 @@ impl Scene for PlacardScene {
                                 BORDER_X_OFFSET,
                                 0,
                                 sub,
+                                text_palette,
                             ),
                             None => plac.draw_offset(
                                 resources.amber_font,
                                 play_canvas,
                                 BORDER_X_OFFSET,
                                 0,
+                                text_palette,
                             ),
                         }
                     }
@@ -157,7 +157,7 @@ impl<'tex> RenderResources<'tex> {
             let mut img_tex = ImageTexture::new(iff, &slot, Rc::downgrade(&image_backing));
 
             let palette = iff.colormap.as_ref().unwrap_or(sys_palette);
-            img_tex.update(palette, iff.transparent_color);
+            img_tex.refresh(palette);
 
             next_x += iff.width as u32;
             row_h = row_h.max(iff.height as u32);
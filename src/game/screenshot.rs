@@ -0,0 +1,243 @@
+// Screenshot capture: read back a render target texture and save it as a PNG.
+//
+// The PNG writer is deliberately minimal: 8-bit RGBA, no filtering, and the
+// zlib stream uses uncompressed (stored) deflate blocks. Files are larger than
+// a real encoder would produce but need no compression library, and a 320x200
+// capture is only ~250 KB.
+
+use sdl3::pixels::PixelFormat;
+use sdl3::render::{Canvas, RenderTarget, Texture};
+
+use std::path::Path;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+const PNG_COLOR_TYPE_RGBA: u8 = 6;
+
+// largest payload of a single stored deflate block
+const STORED_BLOCK_MAX: usize = 0xFFFF;
+
+/// Read the pixels of the target texture `texture` (e.g. the 320x200 play
+/// texture) and write them to `path` as a PNG.
+pub fn capture_target_to_png<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    texture: &mut Texture,
+    path: &Path,
+) -> Result<(), String> {
+    let mut captured = None;
+    canvas
+        .with_texture_canvas(texture, |target| captured = Some(target.read_pixels(None)))
+        .map_err(|e| e.to_string())?;
+    let surface = captured
+        .unwrap()
+        .and_then(|surface| surface.convert_format(PixelFormat::RGBA32))
+        .map_err(|e| e.to_string())?;
+
+    // drop any row padding from the surface pitch
+    let (width, height) = (surface.width() as usize, surface.height() as usize);
+    let pitch = surface.pitch() as usize;
+    let mut rgba: Vec<u8> = Vec::with_capacity(width * height * 4);
+    surface.with_lock(|data| {
+        for row in data.chunks(pitch).take(height) {
+            rgba.extend_from_slice(&row[..width * 4]);
+        }
+    });
+
+    save_png(path, width, height, &rgba)
+}
+
+/// Write an RGBA32 pixel buffer (`width * 4` bytes per row) to `path` as a PNG.
+pub fn save_png(path: &Path, width: usize, height: usize, rgba: &[u8]) -> Result<(), String> {
+    let data = encode_png(width, height, rgba)?;
+    std::fs::write(path, data).map_err(|e| format!("Failed to write PNG {:?}: {}", path, e))
+}
+
+/// Encode an RGBA32 pixel buffer as an 8-bit RGBA PNG file image.
+pub fn encode_png(width: usize, height: usize, rgba: &[u8]) -> Result<Vec<u8>, String> {
+    if width == 0 || height == 0 {
+        return Err(format!("Cannot encode an empty {}x{} PNG", width, height));
+    }
+    if rgba.len() != width * height * 4 {
+        return Err(format!(
+            "PNG pixel buffer is {} bytes, expected {} for {}x{}",
+            rgba.len(),
+            width * height * 4,
+            width,
+            height
+        ));
+    }
+
+    let mut ihdr: Vec<u8> = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(height as u32).to_be_bytes());
+    ihdr.extend_from_slice(&[8, PNG_COLOR_TYPE_RGBA, 0, 0, 0]); // depth, type, compression, filter, interlace
+
+    // each scanline is prefixed with filter type 0 (none)
+    let mut scanlines: Vec<u8> = Vec::with_capacity((width * 4 + 1) * height);
+    for row in rgba.chunks(width * 4).take(height) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut png: Vec<u8> = PNG_SIGNATURE.to_vec();
+    push_chunk(&mut png, b"IHDR", &ihdr);
+    push_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    push_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+fn push_chunk(out: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(id);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// Wrap `data` in a zlib stream made of stored (uncompressed) deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let block_count = data.len().div_ceil(STORED_BLOCK_MAX).max(1);
+    let mut out: Vec<u8> = Vec::with_capacity(data.len() + block_count * 5 + 6);
+    out.extend_from_slice(&[0x78, 0x01]); // deflate, 32K window, no dictionary
+
+    let mut blocks = data.chunks(STORED_BLOCK_MAX).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        out.push(last as u8); // BFINAL, BTYPE = 00
+        out.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFFFFFF;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sdl3::pixels::Color;
+    use sdl3::surface::Surface;
+
+    // Decode a PNG written by `encode_png` back to (width, height, rgba).
+    // Only handles what the encoder emits: RGBA, filter 0, stored blocks.
+    fn decode_png(png: &[u8]) -> (usize, usize, Vec<u8>) {
+        assert_eq!(png[..8], PNG_SIGNATURE);
+        let mut offset = 8;
+        let (mut width, mut height) = (0, 0);
+        let mut zlib: Vec<u8> = Vec::new();
+        while offset < png.len() {
+            let len = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+            let id = &png[offset + 4..offset + 8];
+            let data = &png[offset + 8..offset + 8 + len];
+            let crc =
+                u32::from_be_bytes(png[offset + 8 + len..offset + 12 + len].try_into().unwrap());
+            assert_eq!(crc, crc32(&png[offset + 4..offset + 8 + len]));
+            match id {
+                b"IHDR" => {
+                    width = u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize;
+                    height = u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize;
+                    assert_eq!(data[8..10], [8, PNG_COLOR_TYPE_RGBA]);
+                }
+                b"IDAT" => zlib.extend_from_slice(data),
+                _ => {}
+            }
+            offset += len + 12;
+        }
+
+        let mut scanlines: Vec<u8> = Vec::new();
+        let mut pos = 2;
+        loop {
+            let last = zlib[pos] & 1 != 0;
+            let len = u16::from_le_bytes([zlib[pos + 1], zlib[pos + 2]]) as usize;
+            scanlines.extend_from_slice(&zlib[pos + 5..pos + 5 + len]);
+            pos += 5 + len;
+            if last {
+                break;
+            }
+        }
+        assert_eq!(zlib[pos..pos + 4], adler32(&scanlines).to_be_bytes());
+
+        let mut rgba: Vec<u8> = Vec::new();
+        for row in scanlines.chunks(width * 4 + 1) {
+            assert_eq!(row[0], 0);
+            rgba.extend_from_slice(&row[1..]);
+        }
+        (width, height, rgba)
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xAE426082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+    }
+
+    #[test]
+    fn test_encode_png_round_trip_multiple_blocks() {
+        // large enough that the scanlines need several stored blocks
+        let (width, height) = (200, 120);
+        let rgba: Vec<u8> = (0..width * height * 4)
+            .map(|i| (i * 7 % 251) as u8)
+            .collect();
+        let png = encode_png(width, height, &rgba).unwrap();
+        assert_eq!(decode_png(&png), (width, height, rgba));
+
+        assert!(encode_png(4, 4, &[0; 12]).is_err());
+        assert!(encode_png(0, 4, &[]).is_err());
+        assert!(encode_png(4, 0, &[]).is_err());
+    }
+
+    #[test]
+    fn test_capture_solid_render_target() {
+        let surface = Surface::new(16, 16, PixelFormat::RGBA32).unwrap();
+        let mut canvas = surface.into_canvas().unwrap();
+        let creator = canvas.texture_creator();
+        let mut target = creator
+            .create_texture_target(Some(PixelFormat::RGBA32), 8, 4)
+            .unwrap();
+        canvas
+            .with_texture_canvas(&mut target, |c| {
+                c.set_draw_color(Color::RGBA(0x12, 0x34, 0x56, 0xFF));
+                c.clear();
+            })
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.png");
+        capture_target_to_png(&mut canvas, &mut target, &path).unwrap();
+
+        let (width, height, rgba) = decode_png(&std::fs::read(&path).unwrap());
+        assert_eq!((width, height), (8, 4));
+        assert!(rgba
+            .chunks_exact(4)
+            .all(|px| px == [0x12, 0x34, 0x56, 0xFF]));
+    }
+}
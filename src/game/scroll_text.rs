@@ -0,0 +1,192 @@
+use crate::game::colors::Palette;
+use crate::game::font_texture::FontTexture;
+use crate::game::placard::Placard;
+use crate::game::render_task::RenderTask;
+
+use sdl3::rect::Rect;
+use sdl3::render::Canvas;
+use sdl3::video::Window;
+
+/*
+ * Scrolling text (marquee / credits) render task. The placard text enters from
+ * the bottom (or right) edge of the viewport, moves by a fixed number of pixels
+ * per tick, and the task completes once the last line has left the far edge.
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollDirection {
+    Vertical,   // scroll upward
+    Horizontal, // scroll leftward
+}
+
+pub struct ScrollTextTask<'a, 'tex> {
+    placard: &'a Placard,
+    font: &'a FontTexture<'tex>,
+    palette: &'a Palette,
+    viewport: Rect,
+    direction: ScrollDirection,
+    pixels_per_tick: i32,
+
+    // placard bounds, measured once at construction; None when empty
+    content: Option<Rect>,
+    // pixels scrolled so far
+    offset: i32,
+}
+
+impl<'a, 'tex> ScrollTextTask<'a, 'tex> {
+    pub fn new(
+        placard: &'a Placard,
+        font: &'a FontTexture<'tex>,
+        palette: &'a Palette,
+        viewport: Rect,
+        direction: ScrollDirection,
+        pixels_per_tick: i32,
+    ) -> ScrollTextTask<'a, 'tex> {
+        ScrollTextTask {
+            placard,
+            font,
+            palette,
+            viewport,
+            direction,
+            pixels_per_tick,
+            content: placard.bounds(font.get_font()),
+            offset: 0,
+        }
+    }
+
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+
+    /// Total scroll distance: the content travels across the whole viewport
+    /// and then its own length so it fully exits the far edge.
+    fn scroll_length(&self) -> i32 {
+        let (content_w, content_h) = self
+            .content
+            .map_or((0, 0), |r| (r.width() as i32, r.height() as i32));
+        match self.direction {
+            ScrollDirection::Vertical => self.viewport.height() as i32 + content_h,
+            ScrollDirection::Horizontal => self.viewport.width() as i32 + content_w,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.offset >= self.scroll_length()
+    }
+
+    /// Advance the scroll position by `delta_ticks` worth of movement.
+    /// Returns true if still scrolling, false once the text has scrolled off.
+    pub fn advance(&mut self, delta_ticks: i32) -> bool {
+        self.offset = (self.offset + self.pixels_per_tick * delta_ticks).min(self.scroll_length());
+        !self.is_done()
+    }
+
+    /// Top-left origin of the placard content for the current offset.
+    fn origin(&self) -> (i32, i32) {
+        let (content_x, content_y) = self.content.map_or((0, 0), |r| (r.x(), r.y()));
+        match self.direction {
+            ScrollDirection::Vertical => (
+                self.viewport.x() - content_x,
+                self.viewport.bottom() - content_y - self.offset,
+            ),
+            ScrollDirection::Horizontal => (
+                self.viewport.right() - content_x - self.offset,
+                self.viewport.y() - content_y,
+            ),
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas<Window>) {
+        let (x, y) = self.origin();
+        let prev_clip = canvas.clip_rect();
+        canvas.set_clip_rect(self.viewport);
+        self.placard.draw_offset(self.font, canvas, x, y, self.palette);
+        canvas.set_clip_rect(prev_clip);
+    }
+}
+
+impl RenderTask for ScrollTextTask<'_, '_> {
+    fn update(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        delta_ticks: i32,
+        _area: Option<Rect>,
+    ) -> bool {
+        let running = self.advance(delta_ticks);
+        if running {
+            self.draw(canvas);
+        }
+        running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::font::DiskFont;
+
+    use std::rc::Weak;
+
+    fn test_font() -> FontTexture<'static> {
+        let mut font = DiskFont::new();
+        font.y_size = 8;
+        font.x_size = 8;
+        font.baseline = 6;
+        font.lo_char = 32;
+        font.hi_char = 126;
+        FontTexture::new(&font, &Rect::new(0, 0, 1, 1), Weak::new())
+    }
+
+    #[test]
+    fn test_scroll_advances_and_completes() {
+        // two lines, 8 px tall each, 10 px apart -> content height 18
+        let placard = Placard::from_ssp_bytes(&[128, 6, b'A', 128, 16, b'B', 0]);
+        let font = test_font();
+        let palette = Palette::default();
+        let viewport = Rect::new(0, 0, 100, 20);
+        let mut task = ScrollTextTask::new(
+            &placard,
+            &font,
+            &palette,
+            viewport,
+            ScrollDirection::Vertical,
+            2,
+        );
+
+        assert_eq!(task.offset(), 0);
+        assert!(task.advance(1));
+        assert_eq!(task.offset(), 2);
+        assert!(task.advance(3));
+        assert_eq!(task.offset(), 8);
+
+        // 20 px viewport + 18 px of content = 38 px to scroll off
+        let mut ticks = 0;
+        while task.advance(1) {
+            ticks += 1;
+        }
+        assert_eq!(ticks, 14);
+        assert_eq!(task.offset(), 38);
+        assert!(task.is_done());
+    }
+
+    #[test]
+    fn test_horizontal_scroll_uses_content_width() {
+        let placard = Placard::from_ssp_bytes(&[128, 6, b'A', b'B', b'C', 0]);
+        let font = test_font();
+        let palette = Palette::default();
+        let viewport = Rect::new(0, 0, 40, 10);
+        let mut task = ScrollTextTask::new(
+            &placard,
+            &font,
+            &palette,
+            viewport,
+            ScrollDirection::Horizontal,
+            4,
+        );
+
+        // 40 px viewport + 24 px of text
+        assert!(task.advance(15));
+        assert!(!task.advance(1));
+        assert_eq!(task.offset(), 64);
+    }
+}
@@ -1,5 +1,46 @@
 use crate::game::key_bindings::KeyBindings;
+use sdl3::rect::Rect;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Window size used when none has been saved yet.
+pub const DEFAULT_WINDOW_SIZE: (u32, u32) = (640, 480);
+
+/// Describes a setting that just changed, passed to the change listener.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingChange {
+    Volume(f32),
+    MusicVolume(f32),
+    Muted(bool),
+    Fullscreen(bool),
+    WindowSize((u32, u32)),
+    WindowPosition((i32, i32)),
+}
+
+/// Callback invoked after a setter changes a value.
+pub type ChangeCallback = Box<dyn FnMut(&SettingChange)>;
+
+/// Holds the registered change callback. Not persisted, and not carried over
+/// when settings are cloned.
+#[derive(Default)]
+pub struct ChangeListener(Option<ChangeCallback>);
+
+impl Clone for ChangeListener {
+    fn clone(&self) -> Self {
+        ChangeListener(None)
+    }
+}
+
+impl std::fmt::Debug for ChangeListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0.is_some() {
+            "ChangeListener(Some)"
+        } else {
+            "ChangeListener(None)"
+        })
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameSettings {
@@ -16,6 +57,10 @@ pub struct GameSettings {
     // Non-persistent settings can be added here
     #[serde(skip)]
     pub dirty: bool,
+    #[serde(skip)]
+    change_listener: ChangeListener,
+    #[serde(skip)]
+    last_debounced_save: Option<Instant>,
 }
 
 impl Default for GameSettings {
@@ -29,6 +74,8 @@ impl Default for GameSettings {
             muted: false,
             key_bindings: KeyBindings::default(),
             dirty: false,
+            change_listener: ChangeListener::default(),
+            last_debounced_save: None,
         }
     }
 }
@@ -57,9 +104,45 @@ impl GameSettings {
         Ok(())
     }
 
+    /// Save if dirty, but at most once per `min_interval`, so frequent
+    /// changes (e.g. dragging a volume slider) can be saved periodically
+    /// without rewriting the file on every call. Changes held back are
+    /// written by a later call once the interval has passed.
+    pub fn save_debounced(
+        &mut self,
+        min_interval: Duration,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let settings_path = get_settings_path();
+        if let Some(parent) = settings_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.save_debounced_to(
+            settings_path.to_str().unwrap_or("settings.toml"),
+            min_interval,
+            Instant::now(),
+        )
+    }
+
+    fn save_debounced_to(
+        &mut self,
+        path: &str,
+        min_interval: Duration,
+        now: Instant,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let due = self
+            .last_debounced_save
+            .is_none_or(|last| now.saturating_duration_since(last) >= min_interval);
+        if self.dirty && due {
+            self.save_to_file(path)?;
+            self.last_debounced_save = Some(now);
+        }
+        Ok(())
+    }
+
     fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let data = std::fs::read_to_string(path)?;
-        let settings: GameSettings = toml::from_str(&data)?;
+        let mut settings: GameSettings = toml::from_str(&data)?;
+        settings.sanitize();
         Ok(settings)
     }
 
@@ -70,45 +153,116 @@ impl GameSettings {
         Ok(())
     }
 
+    /// Write the persistent settings to `path` as JSON, for sharing between
+    /// machines or attaching to bug reports. Does not affect `dirty`.
+    pub fn export_json(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let data = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+
+    /// Read settings previously written by `export_json`, clamped the same way
+    /// as a TOML load.
+    pub fn import_json(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let data = std::fs::read_to_string(path)?;
+        let mut settings: GameSettings = serde_json::from_str(&data)?;
+        settings.sanitize();
+        Ok(settings)
+    }
+
+    // Bring hand-edited or imported values back into range.
+    fn sanitize(&mut self) {
+        let defaults = GameSettings::default();
+        for (value, default) in [
+            (&mut self.volume, defaults.volume),
+            (&mut self.music_volume, defaults.music_volume),
+        ] {
+            *value = if value.is_nan() {
+                default
+            } else {
+                value.clamp(0.0, 1.0)
+            };
+        }
+    }
+
+    /// Forget the saved window position if the window frame would not overlap
+    /// any of `displays` (e.g. the monitor it was on has been unplugged), so
+    /// the window opens centered instead of off-screen. Call after `load`.
+    /// An empty display list leaves the position alone.
+    pub fn clamp_to_displays(&mut self, displays: &[Rect]) {
+        let Some((x, y)) = self.window_position else {
+            return;
+        };
+        if displays.is_empty() {
+            return;
+        }
+
+        let (width, height) = self.window_size.unwrap_or(DEFAULT_WINDOW_SIZE);
+        let frame = Rect::new(x, y, width, height);
+        if !displays
+            .iter()
+            .any(|display| display.has_intersection(frame))
+        {
+            self.window_position = None;
+            self.dirty = true;
+        }
+    }
+
+    /// Register a callback to be notified whenever a setter changes a value,
+    /// e.g. so the audio system can apply volume changes immediately.
+    /// Replaces any previously registered listener.
+    pub fn set_change_listener(&mut self, listener: ChangeCallback) {
+        self.change_listener = ChangeListener(Some(listener));
+    }
+
+    fn notify(&mut self, change: SettingChange) {
+        self.dirty = true;
+        if let Some(listener) = self.change_listener.0.as_mut() {
+            listener(&change);
+        }
+    }
+
     pub fn set_volume(&mut self, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
         if self.volume != volume {
-            self.volume = volume.clamp(0.0, 1.0);
-            self.dirty = true;
+            self.volume = volume;
+            self.notify(SettingChange::Volume(volume));
         }
     }
 
     pub fn set_music_volume(&mut self, music_volume: f32) {
+        let music_volume = music_volume.clamp(0.0, 1.0);
         if self.music_volume != music_volume {
-            self.music_volume = music_volume.clamp(0.0, 1.0);
-            self.dirty = true;
+            self.music_volume = music_volume;
+            self.notify(SettingChange::MusicVolume(music_volume));
         }
     }
 
     pub fn set_muted(&mut self, muted: bool) {
         if self.muted != muted {
             self.muted = muted;
-            self.dirty = true;
+            self.notify(SettingChange::Muted(muted));
         }
     }
 
     pub fn set_fullscreen(&mut self, fullscreen: bool) {
         if self.fullscreen != fullscreen {
             self.fullscreen = fullscreen;
-            self.dirty = true;
+            self.notify(SettingChange::Fullscreen(fullscreen));
         }
     }
 
     pub fn set_window_size(&mut self, size: (u32, u32)) {
         if self.window_size != Some(size) {
             self.window_size = Some(size);
-            self.dirty = true;
+            self.notify(SettingChange::WindowSize(size));
         }
     }
 
     pub fn set_window_position(&mut self, position: (i32, i32)) {
         if self.window_position != Some(position) {
             self.window_position = Some(position);
-            self.dirty = true;
+            self.notify(SettingChange::WindowPosition(position));
         }
     }
 }
@@ -159,4 +313,127 @@ mod tests {
         assert_eq!(settings.window_position, Some((100, 100)));
         assert!(settings.dirty);
     }
+
+    #[test]
+    fn test_save_debounced_writes_once_per_interval() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.toml");
+        let path = path.to_str().unwrap();
+        let interval = Duration::from_secs(2);
+        let start = Instant::now();
+        let saved_volume = || GameSettings::load_from_file(path).unwrap().volume;
+
+        let mut settings = GameSettings::new();
+        settings.set_volume(0.5);
+        settings.save_debounced_to(path, interval, start).unwrap();
+        assert_eq!(saved_volume(), 0.5);
+        assert!(!settings.dirty);
+
+        // a second change right away is held back
+        settings.set_volume(0.25);
+        settings
+            .save_debounced_to(path, interval, start + Duration::from_millis(100))
+            .unwrap();
+        assert_eq!(saved_volume(), 0.5);
+        assert!(settings.dirty);
+
+        settings
+            .save_debounced_to(path, interval, start + interval)
+            .unwrap();
+        assert_eq!(saved_volume(), 0.25);
+        assert!(!settings.dirty);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+
+        let mut settings = GameSettings::new();
+        settings.set_volume(0.25);
+        settings.set_music_volume(0.75);
+        settings.set_muted(true);
+        settings.set_fullscreen(true);
+        settings.set_window_size((960, 600));
+        settings.set_window_position((-40, 12));
+        settings.export_json(&path).unwrap();
+
+        let imported = GameSettings::import_json(&path).unwrap();
+        assert_eq!(imported.volume, 0.25);
+        assert_eq!(imported.music_volume, 0.75);
+        assert!(imported.muted);
+        assert!(imported.fullscreen);
+        assert_eq!(imported.window_size, Some((960, 600)));
+        assert_eq!(imported.window_position, Some((-40, 12)));
+        assert!(!imported.dirty);
+    }
+
+    #[test]
+    fn test_json_import_clamps_volume() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        std::fs::write(
+            &path,
+            r#"{"window_position": null, "window_size": null, "fullscreen": false,
+                "volume": 3.5, "music_volume": -1.0, "muted": false}"#,
+        )
+        .unwrap();
+
+        let imported = GameSettings::import_json(&path).unwrap();
+        assert_eq!(imported.volume, 1.0);
+        assert_eq!(imported.music_volume, 0.0);
+    }
+
+    #[test]
+    fn test_clamp_to_displays_resets_offscreen_position() {
+        let displays = [Rect::new(0, 0, 1920, 1080)];
+        let mut settings = GameSettings::new();
+        settings.window_size = Some((800, 600));
+
+        // was on a second monitor to the right that is no longer attached
+        settings.window_position = Some((2200, 100));
+        settings.clamp_to_displays(&displays);
+        assert_eq!(settings.window_position, None);
+        assert!(settings.dirty);
+    }
+
+    #[test]
+    fn test_clamp_to_displays_keeps_visible_position() {
+        let displays = [Rect::new(0, 0, 1920, 1080), Rect::new(1920, 0, 1280, 1024)];
+        let mut settings = GameSettings::new();
+
+        settings.window_position = Some((2200, 100));
+        settings.clamp_to_displays(&displays);
+        assert_eq!(settings.window_position, Some((2200, 100)));
+
+        // partially off the left edge still overlaps the primary display
+        settings.window_position = Some((-300, 50));
+        settings.clamp_to_displays(&displays);
+        assert_eq!(settings.window_position, Some((-300, 50)));
+        assert!(!settings.dirty);
+    }
+
+    #[test]
+    fn test_change_listener_fires_only_on_real_changes() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let changes: Rc<RefCell<Vec<SettingChange>>> = Rc::new(RefCell::new(Vec::new()));
+        let mut settings = GameSettings::new();
+        let sink = changes.clone();
+        settings.set_change_listener(Box::new(move |change| {
+            sink.borrow_mut().push(change.clone())
+        }));
+
+        settings.set_volume(0.5);
+        settings.set_volume(0.5); // no-op
+        settings.set_fullscreen(true);
+        settings.set_fullscreen(true); // no-op
+        settings.set_music_volume(1.5); // clamps to the current 1.0, no-op
+
+        assert_eq!(
+            *changes.borrow(),
+            vec![SettingChange::Volume(0.5), SettingChange::Fullscreen(true)]
+        );
+    }
 }
@@ -161,6 +161,13 @@ pub fn main() -> Result<(), String> {
         .video()
         .expect("Could not initialize SDL3 video subsystem");
 
+    // drop a saved window position that is no longer on any attached display
+    let display_bounds: Vec<sdl3::rect::Rect> = video_subsystem
+        .displays()
+        .map(|displays| displays.iter().filter_map(|d| d.get_bounds().ok()).collect())
+        .unwrap_or_default();
+    settings.clamp_to_displays(&display_bounds);
+
     // Initialize gamepad subsystem so SDL3 generates ControllerButton/Axis events.
     let gamepad_subsystem = sdl_context
         .gamepad()
@@ -182,11 +189,7 @@ pub fn main() -> Result<(), String> {
         }
     }
 
-    let mut width = 640;
-    let mut height = 480;
-    if settings.window_size.is_some() {
-        (width, height) = settings.window_size.unwrap();
-    }
+    let (width, height) = settings.window_size.unwrap_or(settings::DEFAULT_WINDOW_SIZE);
 
     let mut window_builder = video_subsystem.window("The Faery Tale Adventure", width, height);
     window_builder.resizable();